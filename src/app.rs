@@ -1,5 +1,5 @@
 use crate::{
-    Command,
+    Command, IndexCommand, TemplateCommand, TrashCommand,
     app::{config::Config, error::MmemoResult},
 };
 
@@ -7,25 +7,94 @@ pub mod commands;
 pub mod config;
 pub mod error;
 pub mod expand;
+pub mod grep;
+pub mod index;
+pub mod link;
+pub mod memo;
+pub mod output;
 pub mod path_utils;
+pub mod render;
+pub mod slug;
 
 pub mod selector;
 pub mod template;
+pub mod trash;
+pub mod tui;
 
-pub fn run(cmd: Command) -> MmemoResult<()> {
+// dry_runの時はファイルを変更するコマンドが何をするかだけ表示する
+pub fn run(cmd: Command, dry_run: bool) -> MmemoResult<()> {
     match cmd {
         Command::Init => commands::init()?,
         Command::Help => commands::help(),
         Command::Version => commands::version(),
         _ => {
             let config = Config::load()?;
+            if dry_run {
+                index::set_read_only();
+            }
             match cmd {
-                Command::New(s) => commands::new(&config, &s)?,
-                Command::Edit => commands::edit(&config)?,
-                Command::Delete => commands::delete(&config)?,
-                Command::List => commands::list(&config)?,
-                Command::Grep(r) => commands::grep(&config, &r)?,
-                Command::View => commands::view(&config)?,
+                Command::New {
+                    title,
+                    template,
+                    vars,
+                } => commands::new(&config, &title, template.as_deref(), &vars, dry_run)?,
+                Command::Add {
+                    title,
+                    template,
+                    messages,
+                } => commands::add(&config, &title, template.as_deref(), &messages, dry_run)?,
+                Command::Append { query, messages } => {
+                    commands::append(&config, query.as_deref(), &messages, dry_run)?
+                }
+                Command::Journal { date } => commands::journal(&config, date, dry_run)?,
+                Command::Edit { query, tags } => commands::edit(&config, query.as_deref(), &tags)?,
+                Command::Delete { query, tags, yes } => {
+                    commands::delete(&config, query.as_deref(), &tags, yes, dry_run)?
+                }
+                Command::List(options) => commands::list(&config, &options)?,
+                Command::Grep {
+                    patterns,
+                    tags,
+                    format,
+                } => commands::grep(&config, &patterns, &tags, format)?,
+                Command::View {
+                    query,
+                    tags,
+                    follow,
+                } => commands::view(&config, query.as_deref(), &tags, follow)?,
+                Command::Links { query } => commands::links(&config, query.as_deref())?,
+                Command::Check { orphans } => commands::check(&config, orphans)?,
+                Command::Graph(options) => commands::graph(&config, &options)?,
+                Command::Tui => commands::tui(&config, dry_run)?,
+                Command::Search { query, tags } => {
+                    commands::search(&config, query.as_deref(), &tags)?
+                }
+                Command::Cat { query, tags, mode } => {
+                    commands::cat(&config, query.as_deref(), &tags, mode)?
+                }
+                Command::Move {
+                    from,
+                    title,
+                    update_links,
+                    yes,
+                } => commands::mv(&config, from.as_deref(), &title, update_links, yes, dry_run)?,
+                Command::Tags => commands::tags(&config)?,
+                Command::Restore { query } => {
+                    commands::restore(&config, query.as_deref(), dry_run)?
+                }
+                Command::Trash(TrashCommand::List) => commands::trash_list(&config)?,
+                Command::Trash(TrashCommand::Empty { older_than, yes }) => {
+                    commands::trash_empty(&config, older_than, yes, dry_run)?
+                }
+                Command::Template(TemplateCommand::List) => commands::template_list(&config)?,
+                Command::Template(TemplateCommand::New(name)) => {
+                    commands::template_new(&config, &name, dry_run)?
+                }
+                Command::Template(TemplateCommand::Edit(name)) => {
+                    commands::template_edit(&config, name.as_deref())?
+                }
+                Command::Index(IndexCommand::Rebuild) => commands::index_rebuild(&config, dry_run)?,
+                Command::Index(IndexCommand::Status) => commands::index_status(&config)?,
                 Command::Config => commands::config(&config)?,
                 _ => unreachable!(),
             }
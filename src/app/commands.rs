@@ -1,20 +1,30 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
     fs::{self, DirEntry, File},
-    io::{self, BufRead, BufReader, Read},
-    path::Path,
+    io::{self, BufRead, BufReader, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
     process,
+    str::FromStr,
 };
 
-use chrono::{DateTime, Datelike, Utc};
-use termimad::{Alignment, MadSkin};
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, TimeDelta};
 
 use crate::app::{
-    config::{Config, GrepKind, InitStatus, ViewerKind},
+    config::{Config, GrepKind, InitStatus, PreviewKind, ViewerKind},
     error::{MmemoError, MmemoResult},
     expand::HomeDir,
-    path_utils::{config_dir, config_path},
-    selector,
-    template::load_template,
+    grep::{self, GrepQuery, LiveGrep},
+    index::{self, IndexEntry},
+    link::{LinkGraph, Rewrite},
+    memo::{self, Memo},
+    output::{self, GraphFormat, ListColumn, OutputFormat},
+    path_utils::{config_dir, config_path, templates_dir},
+    render,
+    selector::{self, builtin::Builtin, preview::Preview},
+    slug::{self, SlugKind},
+    template::{self, DEFAULT_TEMPLATE, load_template},
+    trash, tui,
 };
 
 pub fn init() -> MmemoResult<()> {
@@ -31,247 +41,1996 @@ pub fn init() -> MmemoResult<()> {
     Ok(())
 }
 
-pub fn new(config: &Config, title: &str) -> MmemoResult<()> {
-    let mut filename = title.replace(" ", "_");
+pub fn new(
+    config: &Config,
+    title: &str,
+    template_name: Option<&str>,
+    vars: &[(String, String)],
+    dry_run: bool,
+) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let filename = slug::filename(title, config.slug, Local::now())?;
+    let filename = slug::unique(&memo_dir, &filename);
+
+    // templateが無くても最低限のfront matterでファイルを作ってからエディタを開く
+    let title = leaf_title(title);
+    let mut template_vars = config.template_vars.clone();
+    template_vars.extend(vars.iter().cloned());
+    let now = Local::now();
+    let content = match choose_template(config, template_name)? {
+        TemplateChoice::File(path) => load_template(title, File::open(path)?, &template_vars, now)?,
+        TemplateChoice::Builtin => {
+            load_template(title, DEFAULT_TEMPLATE.as_bytes(), &template_vars, now)?
+        }
+        TemplateChoice::Cancelled => return Ok(()),
+    };
+
+    create_and_edit(config, &memo_dir, &filename, &content, dry_run)
+}
+
+// contentでファイルを作ってエディタを開く。何も変わっていなければremove_unchangedに従う
+fn create_and_edit(
+    config: &Config,
+    memo_dir: &Path,
+    filename: &str,
+    content: &str,
+    dry_run: bool,
+) -> MmemoResult<()> {
+    let file_path = memo_dir.join(filename);
+    if dry_run {
+        println!("Would create: {}", file_path.display());
+        println!("Would run: {} {}", config.editor, file_path.display());
+        return Ok(());
+    }
+
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&file_path, content)?;
+
+    process::Command::new(&config.editor)
+        .arg(&file_path)
+        .status()?;
+
+    let unchanged = fs::read_to_string(&file_path).is_ok_and(|after| after == content);
+    if unchanged {
+        if config.remove_unchanged {
+            fs::remove_file(&file_path)?;
+            println!("No changes, removed: {}", filename);
+        } else {
+            println!("No changes: {}", filename);
+        }
+    }
+
+    Ok(())
+}
+
+// `<journal_dir>/YYYY-MM-DD.md`を開く。無ければjournal用のtemplateで作る
+pub fn journal(config: &Config, date: NaiveDate, dry_run: bool) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let journal_dir = memo_dir.join(&config.journal_dir);
+    let title = date.format("%Y-%m-%d").to_string();
+    let filename = format!("{}/{}.md", config.journal_dir.to_string_lossy(), title);
+    let file_path = memo_dir.join(&filename);
+
+    if file_path.exists() {
+        if dry_run {
+            println!("Would run: {} {}", config.editor, file_path.display());
+            return Ok(());
+        }
+        process::Command::new(&config.editor)
+            .arg(&file_path)
+            .status()?;
+        return Ok(());
+    }
+
+    // {{date}}は今日ではなくjournalの日付にする
+    let now = date
+        .and_time(Local::now().time())
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(Local::now);
+    let vars = &config.template_vars;
+    let mut content = match journal_template(config)? {
+        Some(path) => load_template(&title, File::open(path)?, vars, now)?,
+        None => load_template(&title, DEFAULT_TEMPLATE.as_bytes(), vars, now)?,
+    };
+
+    if config.journal_carry_over {
+        let items = previous_journal(&journal_dir, date)?
+            .map(|path| fs::read_to_string(path).map(|prev| unchecked_items(&prev)))
+            .transpose()?
+            .unwrap_or_default();
+        if !items.is_empty() {
+            push_block(&mut content, &items.join("\n"));
+        }
+    }
+
+    create_and_edit(config, &memo_dir, &filename, &content, dry_run)
+}
+
+// journal.template、templates/journal.*、どちらも無ければNone（DEFAULT_TEMPLATEを使う）
+fn journal_template(config: &Config) -> MmemoResult<Option<PathBuf>> {
+    if let Some(path) = &config.journal_template {
+        return Ok(Some(path.expand_home()?));
+    }
+    match template::find_named(&templates_dir()?, "journal") {
+        Ok(file) => Ok(Some(templates_dir()?.join(file))),
+        Err(MmemoError::TemplateNotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// dateより前で一番新しいjournal
+fn previous_journal(journal_dir: &Path, date: NaiveDate) -> MmemoResult<Option<PathBuf>> {
+    if !journal_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let mut latest: Option<(NaiveDate, PathBuf)> = None;
+    for entry in fs::read_dir(journal_dir)? {
+        let path = entry?.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Ok(entry_date) = NaiveDate::parse_from_str(stem, "%Y-%m-%d") else {
+            continue;
+        };
+        if path.is_file()
+            && entry_date < date
+            && latest.as_ref().is_none_or(|(d, _)| entry_date > *d)
+        {
+            latest = Some((entry_date, path));
+        }
+    }
+
+    Ok(latest.map(|(_, path)| path))
+}
+
+// `- [ ]`の行（インデントはそのまま）。コードブロックの中は除く
+fn unchecked_items(content: &str) -> Vec<String> {
+    let mut in_fence = false;
+    let mut items = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+        } else if !in_fence && (trimmed.starts_with("- [ ]") || trimmed.starts_with("* [ ]")) {
+            items.push(line.to_string());
+        }
+    }
+    items
+}
+
+// 空行を挟んでtextを末尾に足す
+fn push_block(content: &mut String, text: &str) {
+    if !content.is_empty() && !content.ends_with("\n\n") {
+        content.push_str(if content.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        });
+    }
+    content.push_str(text.trim_end_matches('\n'));
+    content.push('\n');
+}
+
+// -mが無ければパイプされたstdinを読む
+fn capture_text(messages: &[String]) -> MmemoResult<String> {
+    let text = if !messages.is_empty() {
+        messages.join("\n\n")
+    } else if !io::stdin().is_terminal() {
+        let mut buf = String::new();
+        io::stdin().read_to_string(&mut buf)?;
+        buf
+    } else {
+        String::new()
+    };
+
+    if text.trim().is_empty() {
+        return Err(MmemoError::InvalidArgs {
+            message: "nothing to add; pass -m <text> or pipe text to stdin".to_string(),
+        });
+    }
+    Ok(text)
+}
+
+// エディタを開かずにtextを書く。memoが無ければtemplateから作り、あれば末尾に足す
+pub fn add(
+    config: &Config,
+    title: &str,
+    template_name: Option<&str>,
+    messages: &[String],
+    dry_run: bool,
+) -> MmemoResult<()> {
+    let text = capture_text(messages)?;
+    let memo_dir = config.memo_dir.expand_home()?;
+    let filename = slug::filename(title, config.slug, Local::now())?;
+    let filename = existing_memo(&memo_dir, &filename, title)?.unwrap_or(filename);
+    let file_path = memo_dir.join(&filename);
+
+    if file_path.exists() {
+        if dry_run {
+            println!("Would append to: {}", file_path.display());
+            return Ok(());
+        }
+        let mut content = fs::read_to_string(&file_path)?;
+        push_block(&mut content, &text);
+        fs::write(&file_path, content)?;
+        println!("Appended: {}", filename);
+        return Ok(());
+    }
+
+    // selectorは使わない（スクリプトからも呼ぶので）
+    let template = match template_name {
+        Some(name) => Some(templates_dir()?.join(template::find_named(&templates_dir()?, name)?)),
+        None => config
+            .memo_template
+            .as_ref()
+            .map(|p| p.expand_home())
+            .transpose()?,
+    };
+    let title = leaf_title(title);
+    let now = Local::now();
+    let mut content = match template {
+        Some(path) => load_template(title, File::open(path)?, &config.template_vars, now)?,
+        None => load_template(
+            title,
+            DEFAULT_TEMPLATE.as_bytes(),
+            &config.template_vars,
+            now,
+        )?,
+    };
+    push_block(&mut content, &text);
+
+    if dry_run {
+        println!("Would create: {}", file_path.display());
+        return Ok(());
+    }
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&file_path, content)?;
+    println!("Created: {}", filename);
+
+    Ok(())
+}
+
+// addで足す先のmemo。slugが毎回変わる時（timestampなど）も、
+// 同じディレクトリにファイル名かtitleが一致するmemoがあればそれにする
+fn existing_memo(memo_dir: &Path, filename: &str, title: &str) -> MmemoResult<Option<String>> {
+    if memo_dir.join(filename).exists() {
+        return Ok(Some(filename.to_string()));
+    }
+
+    let dir = Path::new(filename).parent();
+    let files: Vec<String> = memo_files(memo_dir, &[])?
+        .into_iter()
+        .filter(|file| Path::new(file).parent() == dir)
+        .collect();
+    let titles = load_titles(memo_dir, &files);
+    let mut found = exact_matches(&files, &titles, leaf_title(title));
+    match found.len() {
+        0 => Ok(None),
+        1 => Ok(found.pop()),
+        _ => Err(MmemoError::AmbiguousMemo {
+            query: title.to_string(),
+            candidates: found,
+        }),
+    }
+}
+
+// `- 2026-10-18 09:30 text`の行を既存のmemoの末尾に足す。2行目以降はインデントする
+pub fn append(
+    config: &Config,
+    query: Option<&str>,
+    messages: &[String],
+    dry_run: bool,
+) -> MmemoResult<()> {
+    let text = capture_text(messages)?;
+    let memo_dir = config.memo_dir.expand_home()?;
+    let files = memo_files(&memo_dir, &[])?;
+    let Some(file) = select_memo(config, &memo_dir, files, query)? else {
+        return Ok(());
+    };
+
+    let mut line = format!("- {} ", Local::now().format("%Y-%m-%d %H:%M"));
+    for (i, row) in text.trim().lines().enumerate() {
+        if i > 0 {
+            line.push_str("\n  ");
+        }
+        line.push_str(row);
+    }
+
+    let file_path = memo_dir.join(&file);
+    if dry_run {
+        println!("Would append to: {}", file_path.display());
+        println!("{}", line);
+        return Ok(());
+    }
+
+    let mut content = fs::read_to_string(&file_path)?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&line);
+    content.push('\n');
+    fs::write(&file_path, content)?;
+    println!("Appended: {}", file);
+
+    Ok(())
+}
+
+enum TemplateChoice {
+    File(PathBuf),
+    Builtin,
+    Cancelled,
+}
+
+// memo_templateとtemplates/の中身。config_dirからの相対パス（外にあれば絶対パス）
+fn template_items(config: &Config) -> MmemoResult<Vec<String>> {
+    let config_dir = config_dir()?;
+    let mut items = Vec::new();
+
+    if let Some(path) = &config.memo_template {
+        let path = path.expand_home()?;
+        let item = path.strip_prefix(&config_dir).unwrap_or(&path);
+        items.push(item.to_string_lossy().to_string());
+    }
+    for name in template::named_templates(&templates_dir()?)? {
+        items.push(format!("templates/{}", name));
+    }
+
+    Ok(items)
+}
+
+// --templateが無ければ、候補が複数ある時だけselectorで選ぶ
+fn choose_template(config: &Config, name: Option<&str>) -> MmemoResult<TemplateChoice> {
+    if let Some(name) = name {
+        let file = template::find_named(&templates_dir()?, name)?;
+        return Ok(TemplateChoice::File(templates_dir()?.join(file)));
+    }
+
+    let config_dir = config_dir()?;
+    let items = template_items(config)?;
+    let item = match items.len() {
+        0 => return Ok(TemplateChoice::Builtin),
+        1 => items.into_iter().next(),
+        _ if !is_interactive() => match &config.memo_template {
+            Some(path) => return Ok(TemplateChoice::File(path.expand_home()?)),
+            None => return Ok(TemplateChoice::Builtin),
+        },
+        _ => {
+            let selector = selector::selector_select(config, &config_dir);
+            selector.select(items, "")?
+        }
+    };
+
+    Ok(match item {
+        Some(item) => TemplateChoice::File(config_dir.join(item)),
+        None => TemplateChoice::Cancelled,
+    })
+}
+
+pub fn template_list(config: &Config) -> MmemoResult<()> {
+    if let Some(path) = &config.memo_template {
+        println!("{:<width$} {}", "(default)", path.display(), width = 20);
+    }
+
+    let dir = templates_dir()?;
+    for file in template::named_templates(&dir)? {
+        let name = Path::new(&file)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| file.clone());
+        println!("{:<width$} {}", name, dir.join(&file).display(), width = 20);
+    }
+
+    Ok(())
+}
+
+pub fn template_new(config: &Config, name: &str, dry_run: bool) -> MmemoResult<()> {
+    let dir = templates_dir()?;
+    let file = slug::filename(name, SlugKind::Unicode, Local::now())?;
+    if file.contains('/') {
+        return Err(MmemoError::InvalidArgs {
+            message: format!("invalid template name: {}", name),
+        });
+    }
+    if template::find_named(&dir, name).is_ok() {
+        return Err(MmemoError::InvalidArgs {
+            message: format!(
+                "template {} already exists (use: mmemo template edit {})",
+                name, name
+            ),
+        });
+    }
+
+    let path = dir.join(&file);
+    if dry_run {
+        println!("Would create: {}", path.display());
+        return Ok(());
+    }
+
+    fs::create_dir_all(&dir)?;
+    fs::write(&path, DEFAULT_TEMPLATE)?;
+
+    process::Command::new(&config.editor).arg(&path).status()?;
+
+    Ok(())
+}
+
+pub fn template_edit(config: &Config, name: Option<&str>) -> MmemoResult<()> {
+    let config_dir = config_dir()?;
+    let path = match name {
+        Some(name) => templates_dir()?.join(template::find_named(&templates_dir()?, name)?),
+        None => {
+            let items = template_items(config)?;
+            if items.is_empty() {
+                return Err(MmemoError::TemplateNotFound(
+                    "no templates (create one with: mmemo template new <name>)".to_string(),
+                ));
+            }
+            match select_interactive(config, &config_dir, items, "", false)?.pop() {
+                Some(item) => config_dir.join(item),
+                None => return Ok(()),
+            }
+        }
+    };
+
+    process::Command::new(&config.editor).arg(path).status()?;
+
+    Ok(())
+}
+
+// `dir/title`の時のtitle部分
+fn leaf_title(title: &str) -> &str {
+    title.rsplit('/').next().unwrap_or(title).trim()
+}
+
+pub fn edit(config: &Config, query: Option<&str>, tags: &[String]) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let files = memo_files(&memo_dir, tags)?;
+
+    if let Some(result) = select_memo(config, &memo_dir, files, query)? {
+        process::Command::new(&config.editor)
+            .current_dir(memo_dir)
+            .arg(result)
+            .status()?;
+    }
+
+    Ok(())
+}
+
+// 一致した行を選んで、その行でエディタを開く
+pub fn search(config: &Config, query: Option<&str>, tags: &[String]) -> MmemoResult<()> {
+    if !is_interactive() {
+        return Err(MmemoError::InvalidArgs {
+            message: "search needs a terminal; use mmemo grep instead".to_string(),
+        });
+    }
+
+    let memo_dir = config.memo_dir.expand_home()?;
+    let files = memo_files(&memo_dir, tags)?;
+    let source = LiveGrep::load(&memo_dir, files.clone())?;
+
+    let preview = (!matches!(config.preview, PreviewKind::None)).then(|| Preview {
+        dir: memo_dir.clone(),
+        markdown: false,
+        position: config.preview_position,
+        width: config.preview_width,
+    });
+    let selector = Builtin { preview };
+
+    let Some(item) = selector.select_live(Box::new(source), query.unwrap_or_default())? else {
+        return Ok(());
+    };
+    let Some((file, line)) = grep::locate(&files, &item) else {
+        return Ok(());
+    };
+
+    open_editor_at(config, &memo_dir, &file, line)
+}
+
+// editor_line_argsの{file}と{line}を置き換えて開く
+fn open_editor_at(config: &Config, memo_dir: &Path, file: &str, line: usize) -> MmemoResult<()> {
+    let args: Vec<String> = config
+        .editor_line_args
+        .split_whitespace()
+        .map(|arg| {
+            arg.replace("{file}", file)
+                .replace("{line}", &line.to_string())
+        })
+        .collect();
+
+    process::Command::new(&config.editor)
+        .current_dir(memo_dir)
+        .args(args)
+        .status()?;
+
+    Ok(())
+}
+
+pub fn delete(
+    config: &Config,
+    query: Option<&str>,
+    tags: &[String],
+    yes: bool,
+    dry_run: bool,
+) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let files = memo_files(&memo_dir, tags)?;
+
+    let selected = select_memos(config, &memo_dir, files, query, true)?;
+    if selected.is_empty() {
+        return Ok(());
+    }
+
+    if dry_run {
+        for file in &selected {
+            println!("Would move to trash: {}", file);
+        }
+        return Ok(());
+    }
+
+    if !yes {
+        for file in &selected {
+            eprintln!("{}", memo_summary(&memo_dir, file));
+        }
+        if !confirm(&format!("Move {} memo(s) to trash?", selected.len()))? {
+            return Ok(());
+        }
+    }
+
+    for file in &selected {
+        trash::trash(&memo_dir, file)?;
+        println!("Moved to trash: {}", file);
+    }
+
+    Ok(())
+}
+
+// 確認用にtitleと本文の最初の数行
+fn memo_summary(memo_dir: &Path, file: &str) -> String {
+    let (title, body) = match Memo::load(memo_dir, file) {
+        Ok(memo) => (memo.title(), memo.body),
+        Err(_) => (
+            String::new(),
+            fs::read_to_string(memo_dir.join(file)).unwrap_or_default(),
+        ),
+    };
+
+    let mut summary = format!("  {}  {}", file, title);
+    for line in body.lines().filter(|l| !l.trim().is_empty()).take(3) {
+        summary.push_str(&format!("\n      {}", line.trim_end()));
+    }
+    summary
+}
+
+pub fn mv(
+    config: &Config,
+    query: Option<&str>,
+    title: &str,
+    update_links: bool,
+    yes: bool,
+    dry_run: bool,
+) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let files = dir_files(&memo_dir)?;
+
+    let Some(from) = select_memo(config, &memo_dir, files, query)? else {
+        return Ok(());
+    };
+
+    let plan = plan_move(config, &memo_dir, &from, title, update_links)?;
+    if dry_run {
+        if plan.from != plan.to {
+            println!("Would move: {} -> {}", plan.from, plan.to);
+        }
+        println!("Would set title: {}", plan.title);
+        print_rewrites("Would update links", &plan.rewrites, &plan.from, &plan.to);
+        return Ok(());
+    }
+
+    // 他のmemoも書き換える時は先に見せて確認する
+    let others = plan.others().count();
+    if others > 0 && !yes {
+        eprintln!("Move: {} -> {}", plan.from, plan.to);
+        for line in rewrite_lines(&plan.rewrites, &plan.from, &plan.to) {
+            eprintln!("{}", line);
+        }
+        if !confirm(&format!("Update links in {} other memo(s)?", others))? {
+            return Ok(());
+        }
+    }
+
+    move_memo(&memo_dir, &plan)?;
+    if plan.from == plan.to {
+        println!("Set title of {}: {}", plan.from, plan.title);
+    } else {
+        println!("Moved: {} -> {}", plan.from, plan.to);
+    }
+    print_rewrites("Updated links", &plan.rewrites, &plan.from, &plan.to);
+
+    Ok(())
+}
+
+// fromをtitleに移す時の変更。move_memoで実行する
+pub struct MovePlan {
+    pub from: String,
+    pub to: String,
+    // front matterに書くtitle
+    pub title: String,
+    // 移動後のfromの内容
+    content: String,
+    // from自身の書き換えが先頭に来る
+    pub rewrites: Vec<Rewrite>,
+}
+
+impl MovePlan {
+    // from以外で書き換えるmemo
+    pub fn others(&self) -> impl Iterator<Item = &Rewrite> {
+        self.rewrites.iter().filter(|r| r.file != self.from)
+    }
+}
+
+// 大文字小文字だけ違う名前でも、ディレクトリにその名前のファイルが無ければfromと同じもの
+fn is_same_file(memo_dir: &Path, from: &str, to: &str) -> bool {
+    if from == to {
+        return true;
+    }
+    let to_path = memo_dir.join(to);
+    if from.to_lowercase() != to.to_lowercase() || !to_path.exists() {
+        return false;
+    }
+    let (Some(dir), Some(name)) = (to_path.parent(), to_path.file_name()) else {
+        return false;
+    };
+    fs::read_dir(dir).is_ok_and(|entries| {
+        !entries
+            .filter_map(Result::ok)
+            .any(|entry| entry.file_name() == name)
+    })
+}
+
+pub fn plan_move(
+    config: &Config,
+    memo_dir: &Path,
+    from: &str,
+    title: &str,
+    update_links: bool,
+) -> MmemoResult<MovePlan> {
+    let to = slug::filename(title, config.slug, Local::now())?;
+    // 同じファイルになる時（大文字小文字を区別しないファイルシステムなど）はtitleだけ変える
+    let to = if is_same_file(memo_dir, from, &to) {
+        from.to_string()
+    } else if memo_dir.join(&to).exists() {
+        return Err(MmemoError::InvalidArgs {
+            message: format!("{} already exists", to),
+        });
+    } else {
+        to
+    };
+
+    // `dir/title`の時はtitleだけをfront matterに書く
+    let new_title = leaf_title(title);
+
+    let content = fs::read_to_string(memo_dir.join(from))?;
+    let rewrites = match update_links {
+        true => {
+            // front matterが無いmemoは移動後のファイル名がtitleになる
+            let moved_title = match Memo::parse(&to, &memo::set_title(&content, new_title)) {
+                Ok(memo) => memo.title(),
+                Err(_) => Path::new(&to)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            };
+            LinkGraph::load(memo_dir)?.rewrites(memo_dir, from, &to, &moved_title)?
+        }
+        false => Vec::new(),
+    };
+
+    let (own, others): (Vec<Rewrite>, Vec<Rewrite>) =
+        rewrites.into_iter().partition(|r| r.file == from);
+    // 自分自身へのリンクや、ディレクトリが変わって直した相対リンク
+    let content = match own.first() {
+        Some(own) => memo::set_title(&own.content, new_title),
+        None => memo::set_title(&content, new_title),
+    };
+
+    Ok(MovePlan {
+        from: from.to_string(),
+        to,
+        title: new_title.to_string(),
+        content,
+        rewrites: own.into_iter().chain(others).collect(),
+    })
+}
+
+// 他のmemoは一時ファイルに書いてから置き換え、最後にfromを消す。
+// 途中で失敗したら置き換えたmemoを元の内容に戻し、toと一時ファイルを消す。
+// プロセスが途中で落ちた時は一時ファイルが残ったり一部だけ書き換わったりする
+pub fn move_memo(memo_dir: &Path, plan: &MovePlan) -> MmemoResult<()> {
+    let to_path = memo_dir.join(&plan.to);
+
+    // (一時ファイル, 置き換えるファイル, 元の内容)
+    let mut pending: Vec<(PathBuf, PathBuf, String)> = Vec::new();
+    let mut replaced = 0;
+    let mut created = false;
+    // 名前が変わらない時はfromも他のmemoと同じように置き換える
+    let in_place = plan.from == plan.to;
+    let result = (|| -> MmemoResult<()> {
+        let own = in_place.then_some((&plan.from, &plan.content));
+        let others = plan.others().map(|r| (&r.file, &r.content));
+        for (file, content) in own.into_iter().chain(others) {
+            let path = memo_dir.join(file);
+            let original = fs::read_to_string(&path)?;
+            let tmp = temporary_path(&path);
+            fs::write(&tmp, content)?;
+            pending.push((tmp, path, original));
+        }
+
+        if !in_place {
+            if let Some(parent) = to_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = File::create_new(&to_path)?;
+            created = true;
+            file.write_all(plan.content.as_bytes())?;
+        }
+
+        for (tmp, path, _) in &pending {
+            fs::rename(tmp, path)?;
+            replaced += 1;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        for (i, (tmp, path, original)) in pending.iter().enumerate() {
+            if i < replaced {
+                let _ = fs::write(path, original);
+            } else {
+                let _ = fs::remove_file(tmp);
+            }
+        }
+        if created {
+            let _ = fs::remove_file(&to_path);
+        }
+        return Err(e);
+    }
+    if in_place {
+        return Ok(());
+    }
+
+    let from_path = memo_dir.join(&plan.from);
+    fs::remove_file(&from_path)?;
+    // 空になったサブディレクトリは消す
+    if let Some(parent) = from_path.parent()
+        && parent != memo_dir
+    {
+        let _ = fs::remove_dir(parent);
+    }
+    Ok(())
+}
+
+// `.name.md.tmp`。dotで始まるのでdir_filesには出てこない
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.tmp", name))
+}
+
+fn print_rewrites(heading: &str, rewrites: &[Rewrite], from: &str, to: &str) {
+    if rewrites.is_empty() {
+        return;
+    }
+    println!("{} in {} memo(s):", heading, rewrites.len());
+    for line in rewrite_lines(rewrites, from, to) {
+        println!("{}", line);
+    }
+}
+
+// `  file:line: old -> new`。移動したmemo自身は移動先のpathで出す
+fn rewrite_lines(rewrites: &[Rewrite], from: &str, to: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for rewrite in rewrites {
+        let file = if rewrite.file == from {
+            to
+        } else {
+            &rewrite.file
+        };
+        for (line, old, new) in &rewrite.changes {
+            lines.push(format!("  {}:{}: {} -> {}", file, line, old, new));
+        }
+    }
+    lines
+}
+
+pub fn restore(config: &Config, query: Option<&str>, dry_run: bool) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let entries = trash::entries(&memo_dir)?;
+    // 名前を指定した時はスクリプトから分かるようにエラーにする
+    if entries.is_empty() && query.is_none() {
+        println!("Trash is empty.");
+        return Ok(());
+    }
+
+    let ids: Vec<String> = match query {
+        Some(query) => {
+            let candidates = match_trash(&entries, query);
+            match candidates.len() {
+                0 => {
+                    return Err(MmemoError::MemoNotFound {
+                        query: query.to_string(),
+                    });
+                }
+                1 => candidates,
+                _ if !is_interactive() => {
+                    return Err(MmemoError::AmbiguousMemo {
+                        query: query.to_string(),
+                        candidates,
+                    });
+                }
+                _ => {
+                    select_interactive(config, &trash::files_dir(&memo_dir), candidates, "", true)?
+                }
+            }
+        }
+        None => {
+            let ids = entries.iter().map(|e| e.id.clone()).collect();
+            select_interactive(config, &trash::files_dir(&memo_dir), ids, "", true)?
+        }
+    };
+
+    for entry in entries.iter().filter(|e| ids.contains(&e.id)) {
+        if dry_run {
+            println!("Would restore: {}", entry.path);
+            continue;
+        }
+        trash::restore(&memo_dir, entry)?;
+        println!("Restored: {}", entry.path);
+    }
+
+    Ok(())
+}
+
+pub fn trash_list(config: &Config) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let entries = trash::entries(&memo_dir)?;
+
+    for entry in &entries {
+        println!(
+            "{:<width$} {}  {}",
+            entry.path,
+            entry.deleted_at_local(),
+            entry.id,
+            width = 40
+        );
+    }
+
+    println!("\nTotal: {} memos in trash", entries.len());
+
+    Ok(())
+}
+
+pub fn index_rebuild(config: &Config, dry_run: bool) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let index = index::rebuild(&memo_dir, !dry_run)?;
+    println!(
+        "{} {} memos ({} terms): {}",
+        if dry_run { "Would index" } else { "Indexed" },
+        index.len(),
+        index.terms(),
+        index::index_path(&memo_dir).display()
+    );
+    Ok(())
+}
+
+pub fn index_status(config: &Config) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let Some((index, stats)) = index::status(&memo_dir)? else {
+        println!("No index. Run: mmemo index rebuild");
+        return Ok(());
+    };
+
+    println!("Index: {}", index::index_path(&memo_dir).display());
+    println!("Memos: {}", index.len());
+    println!("Terms: {}", index.terms());
+    println!(
+        "Pending: {} added, {} updated, {} removed",
+        stats.added, stats.updated, stats.removed
+    );
+    Ok(())
+}
+
+pub fn trash_empty(
+    config: &Config,
+    older_than: Option<TimeDelta>,
+    yes: bool,
+    dry_run: bool,
+) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let targets = trash::expired(&memo_dir, older_than)?;
+    if targets.is_empty() {
+        println!("Nothing to remove.");
+        return Ok(());
+    }
+
+    if dry_run {
+        for entry in &targets {
+            println!(
+                "Would remove: {} ({})",
+                entry.path,
+                entry.deleted_at_local()
+            );
+        }
+        return Ok(());
+    }
+
+    if !yes {
+        for entry in &targets {
+            eprintln!("  {}  {}", entry.path, entry.deleted_at_local());
+        }
+        let message = format!("Permanently remove {} memo(s)?", targets.len());
+        if !confirm(&message)? {
+            return Ok(());
+        }
+    }
+
+    trash::empty(&memo_dir, &targets)?;
+
+    for entry in &targets {
+        println!("Removed: {} ({})", entry.path, entry.deleted_at_local());
+    }
+    println!("\nRemoved {} memos from trash", targets.len());
+
+    Ok(())
+}
+
+pub fn dir_files(dir: &Path) -> MmemoResult<Vec<String>> {
+    let mut files = Vec::new();
+    let mut cd = |entry: &DirEntry| {
+        let file = entry
+            .path()
+            .strip_prefix(dir)
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        files.push(file);
+    };
+    visit_dirs(dir, &mut cd)?;
+
+    Ok(files)
+}
+
+// queryが1件に決まればそれを返し、決まらなければselectorで選ばせる
+fn select_memo(
+    config: &Config,
+    memo_dir: &Path,
+    files: Vec<String>,
+    query: Option<&str>,
+) -> MmemoResult<Option<String>> {
+    let selected = select_memos(config, memo_dir, files, query, false)?;
+    Ok(selected.into_iter().next())
+}
+
+// multiならselectorで複数選べる
+fn select_memos(
+    config: &Config,
+    memo_dir: &Path,
+    files: Vec<String>,
+    query: Option<&str>,
+    multi: bool,
+) -> MmemoResult<Vec<String>> {
+    let Some(query) = query else {
+        return select_interactive(config, memo_dir, files, "", multi);
+    };
+
+    let candidates = match_memos(memo_dir, &files, query);
+    match candidates.len() {
+        0 => Err(MmemoError::MemoNotFound {
+            query: query.to_string(),
+        }),
+        1 => Ok(candidates),
+        _ if !is_interactive() => Err(MmemoError::AmbiguousMemo {
+            query: query.to_string(),
+            candidates,
+        }),
+        _ => select_interactive(config, memo_dir, files, query, multi),
+    }
+}
+
+fn select_interactive(
+    config: &Config,
+    memo_dir: &Path,
+    files: Vec<String>,
+    query: &str,
+    multi: bool,
+) -> MmemoResult<Vec<String>> {
+    if !is_interactive() {
+        return Err(MmemoError::InvalidArgs {
+            message: "not a terminal; pass a memo name to select non-interactively".into(),
+        });
+    }
+
+    let selector = selector::selector_select(config, memo_dir);
+    let selected = if multi {
+        selector.select_multi(files, query)?
+    } else {
+        selector.select(files, query)?.into_iter().collect()
+    };
+    Ok(selected)
+}
+
+fn confirm(message: &str) -> MmemoResult<bool> {
+    if !is_interactive() {
+        return Err(MmemoError::InvalidArgs {
+            message: "not a terminal; pass --yes to confirm".into(),
+        });
+    }
+
+    eprint!("{} [y/N] ", message);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+// match_memosと同じく、元のパス、拡張子なしのパス、idのどれかに完全一致すればそれだけ。
+// 無ければ大文字小文字を無視した部分一致の候補を全部返す
+fn match_trash(entries: &[trash::TrashEntry], query: &str) -> Vec<String> {
+    let exact: Vec<String> = entries
+        .iter()
+        .filter(|e| {
+            let stem = Path::new(&e.path).with_extension("");
+            e.path == query || stem.to_string_lossy() == query || e.id == query
+        })
+        .map(|e| e.id.clone())
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+
+    let query = query.to_lowercase();
+    entries
+        .iter()
+        .filter(|e| e.path.to_lowercase().contains(&query) || e.id.to_lowercase().contains(&query))
+        .map(|e| e.id.clone())
+        .collect()
+}
+
+// パス、拡張子なしのパス、titleのどれかに完全一致すればそれだけ。
+// 無ければ大文字小文字を無視した部分一致の候補を全部返す
+fn match_memos(memo_dir: &Path, files: &[String], query: &str) -> Vec<String> {
+    let titles = load_titles(memo_dir, files);
+    let exact = exact_matches(files, &titles, query);
+    if !exact.is_empty() {
+        return exact;
+    }
+
+    let query = query.to_lowercase();
+    files
+        .iter()
+        .zip(&titles)
+        .filter(|(file, title)| {
+            file.to_lowercase().contains(&query)
+                || title
+                    .as_ref()
+                    .is_some_and(|t| t.to_lowercase().contains(&query))
+        })
+        .map(|(file, _)| file.clone())
+        .collect()
+}
+
+fn load_titles(memo_dir: &Path, files: &[String]) -> Vec<Option<String>> {
+    files
+        .iter()
+        .map(|file| Memo::load(memo_dir, file).ok().map(|memo| memo.title()))
+        .collect()
+}
 
-    let extension = Path::new(&filename).extension();
+// パス、拡張子なしのパス、titleのどれかに完全一致するもの
+fn exact_matches(files: &[String], titles: &[Option<String>], query: &str) -> Vec<String> {
+    files
+        .iter()
+        .zip(titles)
+        .filter(|(file, title)| {
+            let stem = Path::new(file).with_extension("");
+            file.as_str() == query
+                || stem.to_string_lossy() == query
+                || title
+                    .as_ref()
+                    .is_some_and(|t| t.eq_ignore_ascii_case(query))
+        })
+        .map(|(file, _)| file.clone())
+        .collect()
+}
+
+// tagsが空なら全件、指定があれば全てのtagを持つmemoだけ
+// indexがあればそれを使う
+pub fn memo_files(memo_dir: &Path, tags: &[String]) -> MmemoResult<Vec<String>> {
+    if let Some(index) = index::open(memo_dir)? {
+        return Ok(index.files_with_tags(tags));
+    }
+
+    let files = dir_files(memo_dir)?;
+    if tags.is_empty() {
+        return Ok(files);
+    }
+
+    let files = files
+        .into_iter()
+        .filter(|file| {
+            Memo::load(memo_dir, file)
+                .map(|memo| memo.has_tags(tags))
+                .unwrap_or(false)
+        })
+        .collect();
+    Ok(files)
+}
+
+fn visit_dirs(dir: &Path, cb: &mut dyn FnMut(&DirEntry)) -> io::Result<()> {
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with(".") {
+                continue;
+            }
+
+            if path.is_dir() {
+                visit_dirs(&path, cb)?;
+            } else {
+                cb(&entry);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ListSort {
+    #[default]
+    Name,
+    Created,
+    Modified,
+    Size,
+    Title,
+}
+
+pub struct ParseListSortError;
+
+impl FromStr for ListSort {
+    type Err = ParseListSortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" | "path" => Ok(ListSort::Name),
+            "created" | "date" => Ok(ListSort::Created),
+            "modified" => Ok(ListSort::Modified),
+            "size" => Ok(ListSort::Size),
+            "title" => Ok(ListSort::Title),
+            _ => Err(ParseListSortError),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ListOptions {
+    pub tags: Vec<String>,
+    // memo_dirからの相対パス。この下のmemoだけ出す
+    pub dir: Option<String>,
+    pub sort: ListSort,
+    pub reverse: bool,
+    // createdの日付で絞る（両端を含む）
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub limit: Option<usize>,
+    // Noneならconfigのlist.columns
+    pub columns: Option<Vec<ListColumn>>,
+    pub format: OutputFormat,
+}
+
+struct ListEntry {
+    path: String,
+    title: String,
+    // front matterのdate
+    front_date: Option<NaiveDate>,
+    tags: Vec<String>,
+    words: usize,
+    size: u64,
+    // ファイルシステムによってはcreatedが取れない
+    created: Option<DateTime<Local>>,
+    modified: Option<DateTime<Local>>,
+}
+
+impl ListEntry {
+    fn load(memo_dir: &Path, path: &str) -> MmemoResult<Self> {
+        let file_path = memo_dir.join(path);
+        let metadata = fs::metadata(&file_path)?;
+        let content = fs::read_to_string(&file_path).unwrap_or_default();
+        // front matterが壊れていればファイル名をtitleにする
+        let memo = Memo::parse(path, &content).ok();
+        let words = match &memo {
+            Some(memo) => memo.body.split_whitespace().count(),
+            None => content.split_whitespace().count(),
+        };
+
+        Ok(ListEntry {
+            path: path.to_string(),
+            title: match &memo {
+                Some(memo) => memo.title(),
+                None => Path::new(path)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            },
+            front_date: memo.as_ref().and_then(|m| m.date()),
+            tags: memo.as_ref().map(|m| m.tags()).unwrap_or_default(),
+            words,
+            size: metadata.len(),
+            created: metadata.created().ok().map(DateTime::from),
+            modified: metadata.modified().ok().map(DateTime::from),
+        })
+    }
+
+    fn from_index(path: &str, entry: &IndexEntry) -> Self {
+        let time = |nanos: u64| {
+            (nanos > 0).then(|| DateTime::from_timestamp_nanos(nanos as i64).with_timezone(&Local))
+        };
+        ListEntry {
+            path: path.to_string(),
+            title: entry.title.clone(),
+            front_date: entry.date(),
+            tags: entry.tags.clone(),
+            words: entry.words,
+            size: entry.size,
+            created: entry.created.and_then(time),
+            modified: time(entry.modified),
+        }
+    }
+
+    // front matterのdate、無ければ作成日、それも無ければ更新日
+    fn date(&self) -> Option<NaiveDate> {
+        self.front_date
+            .or(self.created.or(self.modified).map(|t| t.date_naive()))
+    }
+
+    // createdは取れないファイルシステムではnull
+    fn to_json(&self) -> serde_json::Value {
+        let timestamp = |time: Option<DateTime<Local>>| {
+            time.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, false))
+        };
+        serde_json::json!({
+            "path": self.path,
+            "title": self.title,
+            "tags": self.tags,
+            "date": self.front_date.map(|d| d.to_string()),
+            "created": timestamp(self.created),
+            "modified": timestamp(self.modified),
+            "size": self.size,
+            "words": self.words,
+        })
+    }
+
+    fn column(&self, column: ListColumn) -> String {
+        match column {
+            ListColumn::Path => self.path.clone(),
+            ListColumn::Title => self.title.clone(),
+            ListColumn::Created => self.date().map(|d| d.to_string()).unwrap_or_default(),
+            ListColumn::Modified => self
+                .modified
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            ListColumn::Tags => self.tags.join(", "),
+            ListColumn::Words => self.words.to_string(),
+            ListColumn::Size => self.size.to_string(),
+        }
+    }
+}
+
+pub fn list(config: &Config, options: &ListOptions) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let index = index::open(&memo_dir)?;
+    let mut files = match &index {
+        Some(index) => index.files_with_tags(&options.tags),
+        None => memo_files(&memo_dir, &options.tags)?,
+    };
+
+    if let Some(dir) = &options.dir {
+        let prefix = dir_prefix(&memo_dir, dir)?;
+        files.retain(|file| file.starts_with(&prefix));
+    }
+
+    let mut entries = Vec::new();
+    for file in &files {
+        let entry = match index.as_ref().and_then(|index| index.get(file)) {
+            Some(indexed) => ListEntry::from_index(file, indexed),
+            None => ListEntry::load(&memo_dir, file)?,
+        };
+        let date = entry.date();
+        let in_range = |bound: Option<NaiveDate>, ok: fn(NaiveDate, NaiveDate) -> bool| {
+            bound.is_none_or(|b| date.is_some_and(|d| ok(d, b)))
+        };
+        if in_range(options.since, |d, b| d >= b) && in_range(options.until, |d, b| d <= b) {
+            entries.push(entry);
+        }
+    }
+
+    // 同じ値の時はpath順
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    match options.sort {
+        ListSort::Name => {}
+        ListSort::Created => entries.sort_by_key(|e| (e.date(), e.created.or(e.modified))),
+        ListSort::Modified => entries.sort_by_key(|e| e.modified),
+        ListSort::Size => entries.sort_by_key(|e| e.size),
+        ListSort::Title => entries.sort_by_cached_key(|e| e.title.to_lowercase()),
+    }
+    if options.reverse {
+        entries.reverse();
+    }
+    if let Some(limit) = options.limit {
+        entries.truncate(limit);
+    }
 
-    if extension.is_none() {
-        filename = format!("{}.md", filename);
+    match options.format {
+        OutputFormat::Text => {}
+        OutputFormat::Tsv => {
+            let records: Vec<_> = entries.iter().map(ListEntry::to_json).collect();
+            return output::write_tsv(
+                &records
+                    .iter()
+                    .map(|r| {
+                        [
+                            "path", "title", "tags", "date", "created", "modified", "size", "words",
+                        ]
+                        .iter()
+                        .map(|key| tsv_value(&r[key]))
+                        .collect()
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+        format => {
+            let records: Vec<_> = entries.iter().map(ListEntry::to_json).collect();
+            return output::write_json(format, &records);
+        }
     }
 
-    // TODO: templateあるなしでファイルの作成の有無が変わってる
-    let file_path = config.memo_dir.expand_home()?.join(&filename);
+    println!("Memos in {}:\n", memo_dir.display());
 
-    if !file_path.exists()
-        && let Some(path) = config.memo_template.clone()
-    {
-        let file = File::open(path.expand_home()?)?;
-        let template = load_template(title, file)?;
-        fs::write(&file_path, template)?;
+    let columns = options.columns.as_ref().unwrap_or(&config.list_columns);
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| columns.iter().map(|c| entry.column(*c)).collect())
+        .collect();
+    // 最後の列以外は一番長い値に揃える（40文字まで）
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+                .min(40)
+        })
+        .collect();
+    for row in &rows {
+        let mut line = String::new();
+        for (i, value) in row.iter().enumerate() {
+            if i + 1 == row.len() {
+                line.push_str(value);
+            } else {
+                line.push_str(&format!("{:<width$} ", value, width = widths[i]));
+            }
+        }
+        println!("{}", line.trim_end());
     }
 
-    process::Command::new(&config.editor)
-        .arg(&file_path)
-        .status()?;
+    println!("\nTotal: {} memos", entries.len());
 
     Ok(())
 }
 
-pub fn edit(config: &Config) -> MmemoResult<()> {
+// memo_dir内のディレクトリを`dir/`の形にする
+fn dir_prefix(memo_dir: &Path, dir: &str) -> MmemoResult<String> {
+    let dir = dir.trim_end_matches('/');
+    let path = Path::new(dir);
+    if path.is_absolute()
+        || path
+            .components()
+            .any(|c| c == std::path::Component::ParentDir)
+        || !memo_dir.join(path).is_dir()
+    {
+        return Err(MmemoError::InvalidArgs {
+            message: format!("no such directory in memo_dir: {}", dir),
+        });
+    }
+    Ok(format!("{}/", dir))
+}
+
+// 配列は`,`で繋げる、nullは空
+fn tsv_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) => {
+            items.iter().map(tsv_value).collect::<Vec<_>>().join(",")
+        }
+        other => other.to_string(),
+    }
+}
+
+pub fn view(
+    config: &Config,
+    query: Option<&str>,
+    tags: &[String],
+    follow: bool,
+) -> MmemoResult<()> {
     let memo_dir = config.memo_dir.expand_home()?;
-    let files = dir_files(&memo_dir)?;
+    let files = memo_files(&memo_dir, tags)?;
 
-    let selector = selector::selector_select(&config.selector);
-    if let Some(result) = selector.select(files)? {
-        process::Command::new(&config.editor)
-            .current_dir(memo_dir)
-            .arg(result)
-            .status()?;
+    let selected = select_memos(config, &memo_dir, files, query, true)?;
+    for (i, result) in selected.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        show(config, &memo_dir, result)?;
+    }
+
+    // 最後に表示したmemoのリンク先を選ぶ。選ばなければ終わる
+    let Some(mut current) = selected.last().cloned().filter(|_| follow) else {
+        return Ok(());
+    };
+    let graph = LinkGraph::load(&memo_dir)?;
+    loop {
+        let mut targets: Vec<String> = Vec::new();
+        for link in graph.outgoing(&current) {
+            if let Some(target) = &link.resolved
+                && !targets.contains(target)
+            {
+                targets.push(target.clone());
+            }
+        }
+        if targets.is_empty() {
+            eprintln!("No links in {}", current);
+            return Ok(());
+        }
+
+        let Some(next) = select_interactive(config, &memo_dir, targets, "", false)?
+            .into_iter()
+            .next()
+        else {
+            return Ok(());
+        };
+        println!();
+        show(config, &memo_dir, &next)?;
+        current = next;
     }
+}
 
+fn show(config: &Config, memo_dir: &Path, file: &str) -> MmemoResult<()> {
+    match config.viewer {
+        ViewerKind::Builtin => {
+            let content = fs::read_to_string(memo_dir.join(file))?;
+            let (width, _) = termimad::terminal_size();
+            for line in render::markdown_lines(&render::memo_text(file, content), width as usize) {
+                println!("{}", line);
+            }
+        }
+        ViewerKind::Glow => {
+            process::Command::new("glow")
+                .current_dir(memo_dir)
+                .arg(file)
+                .status()
+                .map_err(|e| {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        MmemoError::Config {
+                            message: "glow not found. Install glow or use builtin viewer"
+                                .to_string(),
+                        }
+                    } else {
+                        e.into()
+                    }
+                })?;
+        }
+    }
     Ok(())
 }
 
-pub fn delete(config: &Config) -> MmemoResult<()> {
+// 出ているリンクと、このmemoを指しているリンク（backlinks）
+pub fn links(config: &Config, query: Option<&str>) -> MmemoResult<()> {
     let memo_dir = config.memo_dir.expand_home()?;
-    let files = dir_files(&memo_dir)?;
+    let files = memo_files(&memo_dir, &[])?;
+    let Some(file) = select_memo(config, &memo_dir, files, query)? else {
+        return Ok(());
+    };
 
-    let selector = selector::selector_select(&config.selector);
-    if let Some(result) = selector.select(files)? {
-        process::Command::new("rm")
-            .current_dir(memo_dir)
-            .arg(result)
-            .status()?;
+    let graph = LinkGraph::load(&memo_dir)?;
+    println!("{} ({})", file, graph.title(&file).unwrap_or_default());
+
+    let outgoing = graph.outgoing(&file);
+    println!("\nLinks ({}):", outgoing.len());
+    for link in outgoing {
+        let target = link.resolved.as_deref().unwrap_or("(not found)");
+        println!("  {:>4}: {} -> {}", link.line, link.label(), target);
     }
 
+    let backlinks = graph.backlinks(&file);
+    println!("\nBacklinks ({}):", backlinks.len());
+    for (from, link) in backlinks {
+        println!("  {}:{}: {}", from, link.line, link.label());
+    }
     Ok(())
 }
 
-pub fn dir_files(dir: &Path) -> MmemoResult<Vec<String>> {
-    let mut files = Vec::new();
-    let mut cd = |entry: &DirEntry| {
-        let file = entry
-            .path()
-            .strip_prefix(dir)
-            .unwrap()
-            .to_string_lossy()
-            .to_string();
+// 壊れたリンク、front matterの不備、titleの重複、どこからもリンクされていないmemoを調べる
+// 問題があればエラー（終了コード1）にする
+pub fn check(config: &Config, orphans: bool) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+    let (memos, problems) = find_problems(&memo_dir, orphans)?;
+    report_problems(memos, &problems)
+}
 
-        files.push(file);
-    };
-    visit_dirs(dir, &mut cd)?;
+// lineが0ならファイル全体の問題
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Problem {
+    file: String,
+    line: usize,
+    message: String,
+}
 
-    Ok(files)
+impl Problem {
+    fn new(file: &str, line: usize, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_string(),
+            line,
+            message: message.into(),
+        }
+    }
 }
 
-fn visit_dirs(dir: &Path, cb: &mut dyn FnMut(&DirEntry)) -> io::Result<()> {
-    if dir.is_dir() {
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if entry.file_name().to_string_lossy().starts_with(".") {
-                continue;
+// 調べたmemoの数と、path・行順の問題
+fn find_problems(memo_dir: &Path, orphans: bool) -> MmemoResult<(usize, Vec<Problem>)> {
+    let graph = LinkGraph::load(memo_dir)?;
+
+    let mut problems = Vec::new();
+    let mut titles: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    let mut memos = 0;
+    for file in graph.files() {
+        for link in graph.outgoing(file) {
+            if link.resolved.is_none() {
+                problems.push(Problem::new(
+                    file,
+                    link.line,
+                    format!("broken link {}", link.label()),
+                ));
             }
+        }
 
-            if path.is_dir() {
-                visit_dirs(&path, cb)?;
-            } else {
-                cb(&entry);
+        // .md以外（画像など）は添付ファイルとしてリンクだけ見る
+        if !file.to_lowercase().ends_with(".md") {
+            continue;
+        }
+        memos += 1;
+
+        match Memo::load(memo_dir, file) {
+            Ok(memo) if memo.front_matter.is_none() => {
+                problems.push(Problem::new(file, 0, "missing front matter"));
+            }
+            Ok(_) => {}
+            Err(MmemoError::Parse { message }) => {
+                let message = message
+                    .strip_prefix(&format!("{}: ", file))
+                    .unwrap_or(&message);
+                problems.push(Problem::new(
+                    file,
+                    0,
+                    format!("malformed front matter: {}", message),
+                ));
             }
+            Err(e) => problems.push(Problem::new(file, 0, e.to_string())),
         }
-    }
-    Ok(())
-}
 
-pub fn list(config: &Config) -> MmemoResult<()> {
-    let memo_dir = config.memo_dir.expand_home()?;
+        if let Some(title) = graph.title(file) {
+            titles.entry(title.to_lowercase()).or_default().push(file);
+        }
+        if orphans && graph.backlinks(file).is_empty() {
+            problems.push(Problem::new(file, 0, "orphan (no links to this memo)"));
+        }
+    }
 
-    println!("Memos in {}:\n", memo_dir.display());
+    for files in titles.values().filter(|files| files.len() > 1) {
+        for file in files {
+            let others: Vec<&str> = files.iter().copied().filter(|f| f != file).collect();
+            problems.push(Problem::new(
+                file,
+                0,
+                format!(
+                    "duplicate title \"{}\" (also {})",
+                    graph.title(file).unwrap_or_default(),
+                    others.join(", ")
+                ),
+            ));
+        }
+    }
 
-    let files = dir_files(&memo_dir)?;
+    problems.sort();
+    Ok((memos, problems))
+}
 
-    for file in &files {
-        let file_path = memo_dir.join(file);
-        let f = File::open(file_path)?;
-
-        let date_time: DateTime<Utc> = f.metadata()?.created()?.into();
-        let created_time = format!(
-            "{}-{:02}-{:02}",
-            date_time.year(),
-            date_time.month(),
-            date_time.day()
-        );
+fn report_problems(memos: usize, problems: &[Problem]) -> MmemoResult<()> {
+    for problem in problems {
+        match problem.line {
+            0 => println!("{}: {}", problem.file, problem.message),
+            line => println!("{}:{}: {}", problem.file, line, problem.message),
+        }
+    }
 
-        println!("{:<width$} {}", file, created_time, width = 40)
+    if !problems.is_empty() {
+        return Err(MmemoError::CheckFailed {
+            problems: problems.len(),
+        });
     }
+    println!("No problems in {} memos", memos);
+    Ok(())
+}
 
-    println!("\nTotal: {} memos", files.len());
+// tuiの操作はその場で書き換えるので--dry-runは受け付けない
+pub fn tui(config: &Config, dry_run: bool) -> MmemoResult<()> {
+    if dry_run {
+        return Err(MmemoError::InvalidArgs {
+            message: "--dry-run is not supported by tui".to_string(),
+        });
+    }
+    if !is_interactive() {
+        return Err(MmemoError::InvalidArgs {
+            message: "tui needs a terminal".to_string(),
+        });
+    }
+    let memo_dir = config.memo_dir.expand_home()?;
+    tui::run(config, &memo_dir)
+}
 
-    Ok(())
+#[derive(Debug)]
+pub struct GraphOptions {
+    // 全てのtagを持つmemoだけ
+    pub tags: Vec<String>,
+    // memo_dirからの相対パス。この下のmemoだけ
+    pub dir: Option<String>,
+    // tagもnodeにして、memoからtagへのedgeを出す
+    pub tag_nodes: bool,
+    pub format: GraphFormat,
 }
 
-pub fn view(config: &Config) -> MmemoResult<()> {
+// memoをnode、リンクをedgeにして出す。対象外のmemoへのリンクと壊れたリンクは出さない
+pub fn graph(config: &Config, options: &GraphOptions) -> MmemoResult<()> {
     let memo_dir = config.memo_dir.expand_home()?;
-    let files = dir_files(&memo_dir)?;
+    let prefix = match &options.dir {
+        Some(dir) => Some(dir_prefix(&memo_dir, dir)?),
+        None => None,
+    };
+    let graph = LinkGraph::load(&memo_dir)?;
+    write_graph(&mut io::stdout().lock(), &graph, options, prefix.as_deref())
+}
+
+// prefixはmemo_dirからの相対パスの前方一致
+fn write_graph(
+    out: &mut impl Write,
+    graph: &LinkGraph,
+    options: &GraphOptions,
+    prefix: Option<&str>,
+) -> MmemoResult<()> {
+    // .md以外（画像など）はnodeにしない
+    let memos: Vec<&str> = graph
+        .files()
+        .filter(|file| file.to_lowercase().ends_with(".md"))
+        .filter(|file| prefix.is_none_or(|p| file.starts_with(p)))
+        .filter(|file| {
+            let own = graph.tags(file);
+            options
+                .tags
+                .iter()
+                .all(|tag| own.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        })
+        .map(String::as_str)
+        .collect();
+
+    let mut links: BTreeSet<(&str, &str)> = BTreeSet::new();
+    let mut tags: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for &file in &memos {
+        for link in graph.outgoing(file) {
+            if let Some(target) = link.resolved.as_deref()
+                && target != file
+                && memos.contains(&target)
+            {
+                links.insert((file, target));
+            }
+        }
+        if options.tag_nodes {
+            for tag in graph.tags(file) {
+                tags.entry(tag.to_lowercase()).or_default().push(file);
+            }
+        }
+    }
 
-    let selector = selector::selector_select(&config.selector);
-    if let Some(result) = selector.select(files)? {
-        match config.viewer {
-            ViewerKind::Builtin => {
-                let mut file = File::open(memo_dir.join(result))?;
-                let mut buf = String::new();
-                file.read_to_string(&mut buf)?;
-                let mut skin = MadSkin::default();
-                for header in &mut skin.headers {
-                    header.align = Alignment::Left;
+    let title = |file: &str| graph.title(file).unwrap_or(file).to_string();
+    match options.format {
+        GraphFormat::Dot => {
+            let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+            writeln!(out, "digraph mmemo {{")?;
+            for &file in &memos {
+                writeln!(out, "  {} [label={}];", quote(file), quote(&title(file)))?;
+            }
+            for tag in tags.keys() {
+                let id = format!("tag:{}", tag);
+                let label = format!("#{}", tag);
+                writeln!(
+                    out,
+                    "  {} [label={}, shape=box];",
+                    quote(&id),
+                    quote(&label)
+                )?;
+            }
+            for (from, to) in &links {
+                writeln!(out, "  {} -> {};", quote(from), quote(to))?;
+            }
+            for (tag, files) in &tags {
+                for file in files {
+                    let id = format!("tag:{}", tag);
+                    writeln!(
+                        out,
+                        "  {} -> {} [style=dashed, arrowhead=none];",
+                        quote(file),
+                        quote(&id)
+                    )?;
+                }
+            }
+            writeln!(out, "}}")?;
+        }
+        GraphFormat::Json => {
+            let mut nodes: Vec<serde_json::Value> = memos
+                .iter()
+                .map(|file| serde_json::json!({"id": file, "type": "memo", "title": title(file)}))
+                .collect();
+            nodes.extend(
+                tags.keys()
+                    .map(|tag| serde_json::json!({"id": format!("tag:{}", tag), "type": "tag", "title": tag})),
+            );
+            let mut edges: Vec<serde_json::Value> = links
+                .iter()
+                .map(|(from, to)| serde_json::json!({"source": from, "target": to, "type": "link"}))
+                .collect();
+            for (tag, files) in &tags {
+                edges.extend(files.iter().map(|file| {
+                    serde_json::json!({"source": file, "target": format!("tag:{}", tag), "type": "tag"})
+                }));
+            }
+            let graph = serde_json::json!({"nodes": nodes, "edges": edges});
+            let json = serde_json::to_string(&graph).map_err(io::Error::other)?;
+            writeln!(out, "{}", json)?;
+        }
+        GraphFormat::Mermaid => {
+            // idに使えない文字があるので番号にする。ラベルの`"`や`#`、`<>`は実体参照にする
+            let label = |s: &str| {
+                s.replace('#', "#35;")
+                    .replace('"', "#quot;")
+                    .replace('<', "#lt;")
+                    .replace('>', "#gt;")
+            };
+            let id = |file: &str| memos.iter().position(|f| *f == file).unwrap_or_default();
+            writeln!(out, "graph LR")?;
+            for (i, file) in memos.iter().enumerate() {
+                writeln!(out, "  n{}[\"{}\"]", i, label(&title(file)))?;
+            }
+            for (i, tag) in tags.keys().enumerate() {
+                writeln!(out, "  t{}([\"{}\"])", i, label(&format!("#{}", tag)))?;
+            }
+            for (from, to) in &links {
+                writeln!(out, "  n{} --> n{}", id(from), id(to))?;
+            }
+            for (i, files) in tags.values().enumerate() {
+                for file in files {
+                    writeln!(out, "  n{} -.- t{}", id(file), i)?;
                 }
-                skin.print_text(&buf);
-            }
-            ViewerKind::Glow => {
-                process::Command::new("glow")
-                    .current_dir(memo_dir)
-                    .arg(result)
-                    .status()
-                    .map_err(|e| {
-                        if e.kind() == std::io::ErrorKind::NotFound {
-                            MmemoError::Config {
-                                message: "glow not found. Install glow or use builtin viewer"
-                                    .to_string(),
-                            }
-                        } else {
-                            e.into()
-                        }
-                    })?;
             }
         }
     }
     Ok(())
 }
 
-pub fn grep(config: &Config, rest: &[String]) -> MmemoResult<()> {
+#[derive(Debug, Clone, Copy)]
+pub enum CatMode {
+    // ファイルの内容そのまま
+    Raw,
+    // front matterを除いた本文
+    Body,
+    // front matterだけをJSONで（1 memo 1行）
+    FrontMatterJson,
+}
+
+// viewと違って描画せずにstdoutへ出す。端末の時だけ複数memoの間に見出しを入れる
+pub fn cat(
+    config: &Config,
+    query: Option<&str>,
+    tags: &[String],
+    mode: CatMode,
+) -> MmemoResult<()> {
     let memo_dir = config.memo_dir.expand_home()?;
+    let files = memo_files(&memo_dir, tags)?;
+    let selected = select_memos(config, &memo_dir, files, query, true)?;
 
-    match config.grep {
-        GrepKind::Builtin => {
-            if rest.iter().any(|s| s.is_empty()) {
-                return Err(MmemoError::InvalidArgs {
-                    message: "empty pattern".into(),
-                });
+    let tty = io::stdout().is_terminal();
+    let mut out = io::stdout().lock();
+    for (i, file) in selected.iter().enumerate() {
+        let content = fs::read_to_string(memo_dir.join(file))?;
+        let text = match mode {
+            CatMode::Raw => content,
+            CatMode::Body => Memo::parse(file, &content)?
+                .body
+                .trim_start_matches(['\r', '\n'])
+                .to_string(),
+            CatMode::FrontMatterJson => {
+                let json = Memo::parse(file, &content)?
+                    .front_matter
+                    .map(|fm| fm.to_json())
+                    .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+                let json = if tty && selected.len() == 1 {
+                    serde_json::to_string_pretty(&json)
+                } else {
+                    serde_json::to_string(&json)
+                };
+                writeln!(out, "{}", json.map_err(io::Error::other)?)?;
+                continue;
             }
+        };
 
-            if rest.iter().any(|a| a.starts_with('-')) {
-                return Err(MmemoError::InvalidArgs {
-                    message: "builtin grep does not support options (set grep = \"ripgrep\")"
-                        .into(),
-                });
+        if tty && selected.len() > 1 {
+            if i > 0 {
+                writeln!(out)?;
             }
+            writeln!(out, "==> {} <==", file)?;
+        }
+        out.write_all(text.as_bytes())?;
+        if tty && !text.ends_with('\n') {
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn grep(
+    config: &Config,
+    rest: &[String],
+    tags: &[String],
+    format: OutputFormat,
+) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
 
-            let files = dir_files(&memo_dir)?;
+    // --formatはbuiltinで探した結果を出す
+    match (&config.grep, format) {
+        (GrepKind::Builtin, _)
+        | (GrepKind::Rg, OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Tsv) => {
+            let query = GrepQuery::parse(rest)?;
+            // indexがあれば単語を含まないmemoは読まない
+            let files = match index::open(&memo_dir)? {
+                Some(index) => {
+                    let files = index.files_with_tags(tags);
+                    match query.required_words() {
+                        Some(words) => index.candidates(files, &words),
+                        None => files,
+                    }
+                }
+                None => memo_files(&memo_dir, tags)?,
+            };
+            let color = output::color_enabled();
+            let mut records = Vec::new();
 
             for file in files {
                 let f = File::open(memo_dir.join(&file))?;
-                let reader = BufReader::new(f);
-
-                let lines: Vec<_> = reader
-                    .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line = line.ok()?;
-                        rest.iter().all(|r| line.contains(r)).then(|| (i + 1, line))
-                    })
-                    .collect();
+                let lines: Vec<String> = BufReader::new(f).lines().map_while(Result::ok).collect();
+                let matched: Vec<bool> = lines.iter().map(|line| query.is_match(line)).collect();
+
+                if !matched.contains(&true) {
+                    continue;
+                }
 
-                if !lines.is_empty() {
-                    println!("{}", file);
+                if format != OutputFormat::Text {
+                    for (i, line) in lines.iter().enumerate().filter(|(i, _)| matched[*i]) {
+                        let ranges = query.ranges(line);
+                        records.push(serde_json::json!({
+                            "path": file,
+                            "line": i + 1,
+                            "column": ranges.first().map(|(s, _)| s + 1).unwrap_or(1),
+                            "text": line,
+                            "ranges": ranges.iter().map(|(s, e)| [s, e]).collect::<Vec<_>>(),
+                        }));
+                    }
+                    continue;
+                }
 
-                    for (row, line) in lines {
-                        println!("{}: {}", row, highlight_all(&line, rest));
+                // 一致した行は`12: `、前後の行は`12- `。離れたまとまりの間は`--`
+                // 空行は後ろに空白を付けない
+                println!("{}", file);
+                let mut next = 0;
+                for (i, _) in matched.iter().enumerate().filter(|(_, m)| **m) {
+                    let start = i.saturating_sub(query.before).max(next);
+                    let end = (i + query.after).min(lines.len() - 1);
+                    if next > 0 && start > next && (query.before > 0 || query.after > 0) {
+                        println!("--");
+                    }
+                    for row in start..=end {
+                        let line = &lines[row];
+                        let mark = if matched[row] { ':' } else { '-' };
+                        if line.is_empty() {
+                            println!("{}{}", row + 1, mark);
+                        } else if matched[row] && color {
+                            println!("{}: {}", row + 1, highlight(line, &query.ranges(line)));
+                        } else {
+                            println!("{}{} {}", row + 1, mark, line);
+                        }
                     }
-                    println!();
+                    next = next.max(end + 1);
                 }
+                println!();
+            }
+
+            match format {
+                OutputFormat::Text => {}
+                // rangesは`start-end`を`,`で繋げる
+                OutputFormat::Tsv => output::write_tsv(
+                    &records
+                        .iter()
+                        .map(|r| {
+                            let mut row: Vec<String> = ["path", "line", "column", "text"]
+                                .iter()
+                                .map(|key| tsv_value(&r[key]))
+                                .collect();
+                            let ranges = r["ranges"].as_array().into_iter().flatten();
+                            row.push(
+                                ranges
+                                    .map(|range| format!("{}-{}", range[0], range[1]))
+                                    .collect::<Vec<_>>()
+                                    .join(","),
+                            );
+                            row
+                        })
+                        .collect::<Vec<_>>(),
+                )?,
+                _ => output::write_json(format, &records)?,
             }
         }
-        GrepKind::Rg => {
-            process::Command::new("rg")
-                .current_dir(memo_dir)
-                .args(rest)
-                .status()?;
+        (GrepKind::Rg, OutputFormat::Text) => {
+            let mut cmd = process::Command::new("rg");
+            cmd.current_dir(&memo_dir);
+            // rgはNO_COLORを見ないので渡す
+            if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+                cmd.arg("--color=never");
+            }
+            cmd.args(rest);
+            if !tags.is_empty() {
+                let files = memo_files(&memo_dir, tags)?;
+                if files.is_empty() {
+                    return Ok(());
+                }
+                cmd.arg("--").args(files);
+            }
+            cmd.status()?;
         }
     }
 
     Ok(())
 }
 
-fn highlight_all(line: &str, needles: &[String]) -> String {
-    let mut ranges: Vec<(usize, usize)> = Vec::new();
-
-    for n in needles {
-        if n.is_empty() {
-            continue;
-        }
-        for (start, _) in line.match_indices(n) {
-            ranges.push((start, start + n.len()));
-        }
-    }
-
-    if ranges.is_empty() {
-        return line.to_string();
-    }
-
-    // 位置順に並べて、重なりはマージ（“赤くする領域の和集合”にする）
-    ranges.sort_unstable_by_key(|(s, e)| (*s, *e));
-    let mut merged: Vec<(usize, usize)> = Vec::new();
-    for (s, e) in ranges {
-        match merged.last_mut() {
-            Some((ls, le)) if s <= *le => *le = (*le).max(e),
-            _ => merged.push((s, e)),
-        }
-    }
-
-    let mut out = String::with_capacity(line.len() + merged.len() * 10);
+fn highlight(line: &str, ranges: &[(usize, usize)]) -> String {
+    let mut out = String::with_capacity(line.len() + ranges.len() * 10);
     let mut cur = 0;
-    for (s, e) in merged {
+    for &(s, e) in ranges {
         out.push_str(&line[cur..s]);
         out.push_str("\x1b[31m");
         out.push_str(&line[s..e]);
@@ -282,6 +2041,43 @@ fn highlight_all(line: &str, needles: &[String]) -> String {
     out
 }
 
+pub fn tags(config: &Config) -> MmemoResult<()> {
+    let memo_dir = config.memo_dir.expand_home()?;
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut count = |tags: &[String]| {
+        for tag in tags {
+            *counts.entry(tag.to_lowercase()).or_default() += 1;
+        }
+    };
+    match index::open(&memo_dir)? {
+        Some(index) => {
+            for file in index.files_with_tags(&[]) {
+                count(&index.get(&file).map(|e| e.tags.clone()).unwrap_or_default());
+            }
+        }
+        None => {
+            for file in dir_files(&memo_dir)? {
+                let Ok(memo) = Memo::load(&memo_dir, &file) else {
+                    continue;
+                };
+                count(&memo.tags());
+            }
+        }
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a_tag, a), (b_tag, b)| b.cmp(a).then(a_tag.cmp(b_tag)));
+
+    for (tag, count) in &counts {
+        println!("{:<width$} {}", tag, count, width = 40);
+    }
+
+    println!("\nTotal: {} tags", counts.len());
+
+    Ok(())
+}
+
 pub fn config(config: &Config) -> MmemoResult<()> {
     process::Command::new(&config.editor)
         .current_dir(config_dir()?)
@@ -298,16 +2094,54 @@ USAGE:
 COMMANDS:
     init, i              Initialize configuration and create config.toml
     new, n <title...>    Create a new memo (spaces are allowed)
-    list, l              List all memos
-    edit, e              Select and edit a memo
-    view, v              Select and view a memo
+        [--template <name>] [--var key=value]
+    add, a <title...>    Create or append to a memo without opening the editor
+        [--template <name>] [-m, --message <text>]
+    append [name]        Append a timestamped line to a memo
+        [-m, --message <text>]
+    today                Open today's journal entry (created if missing)
+    journal, j           Open a journal entry
+        [--date <YYYY-MM-DD> | --yesterday]
+    list, l [dir]        List memos (only those under dir when given)
+        [--sort name|created|modified|size|title] [--reverse]
+        [--since <YYYY-MM-DD>] [--until <YYYY-MM-DD>] [--limit <n>] [--columns <list>]
+    edit, e [name]       Select and edit a memo
+    view, v [name]       Select and view a memo
+        [--follow]
+    links [name]         Show a memo's links and the memos linking to it
+    check [--no-orphans] Report broken links, bad front matter, duplicate titles and orphans
+    graph [dir]          Print the link graph (--format dot|json|mermaid, --tags, --tag <tag>)
+    cat, show [name]     Print a memo as-is to stdout
+        [--no-front-matter | --front-matter-json]
     grep, g <pat...>     Search memos
-    delete, d            Select and delete a memo
+    tui                  Full-screen memo list with preview (new/edit/rename/tags/delete)
+    search, s [pat...]   Search interactively and open the editor at the selected line
+                         (same as grep --interactive)
+    mv, rename <title...>
+                         Select a memo and rename/move it (--from <name> to skip the selector,
+                         --no-update-links to leave links to it as they are)
+    delete, d [name]     Select and move a memo to the trash
+    tags, t              List tags with memo counts
+    restore [name]       Restore a memo from the trash
+    trash [list]         List memos in the trash
+    trash empty          Permanently remove memos in the trash
+        [--older-than <30d|12h|2w>]
+    template [list]      List templates
+    template new <name>  Create a named template and open it in your editor
+    template edit [name] Edit a template
+    index [status]       Show the search index and how many memos changed since it was updated
+    index rebuild        Create or rebuild the search index
     config, c            Open config.toml in your editor
 
+OPTIONS:
+    --tag <tag>          Only memos with the tag (list/edit/view/cat/grep/delete/graph, repeatable)
+    -y, --yes            Do not ask for confirmation (delete, trash empty)
+    --format <fmt>       Output format for list/grep: text, json, jsonl or tsv
+
 GLOBAL OPTIONS:
     -h, --help           Show help
     -v, --version        Show version
+    --dry-run            Show what new/add/append/today/journal/mv/delete/restore/trash empty/index rebuild would do without changing files
 
 NOTES:
     The behavior of some commands depends on config.toml.
@@ -316,15 +2150,125 @@ NOTES:
       - selector = "builtin"  : use builtin selector
       - selector = "fzf"      : use external "fzf"
       - selector = "skim"     : use external "sk" (skim)
+      - the builtin selector previews the highlighted memo
+        (preview = "markdown" | "raw" | "none", scroll with Ctrl-D/Ctrl-U)
+      - view/delete accept several memos (Tab in builtin, --multi in fzf/skim)
+      - edit/view/delete <name> open the memo directly when the name matches
+        exactly one memo (path, path without extension or title); otherwise the
+        selector opens with the name as the initial query
+      - without a terminal, an ambiguous or missing name is an error
 
     view:
       - viewer = "builtin"    : render markdown in terminal
       - viewer = "glow"       : use external "glow" command
+      - --follow (-f) picks one of the memo's links with the selector and views it,
+        until the selector is closed
+
+    links:
+      - [[title]], [[path/name]] (extension optional) or [[name|shown text]] link to a memo
+        by path, then title, then file name; relative Markdown links like [text](../b.md) too
+      - links in code are ignored; the builtin viewer highlights [[...]] links
+
+    check:
+      - reports broken links, .md files with missing or malformed front matter, memos sharing
+        a title (ignoring case) and memos no other memo links to (skip with --no-orphans)
+      - prints one "path[:line]: problem" per line and exits with status 1 when anything is found
+
+    graph:
+      - nodes are .md memos (under dir / with every --tag when given), edges are resolved links
+        between them; --tags adds "tag:<name>" nodes linked to the memos that have the tag
+      - --format dot (default, for Graphviz), json ({"nodes": [...], "edges": [...]}) or mermaid
+
+    list:
+      - sorted by path by default; --sort created|modified|size|title, --reverse for descending
+      - "created" is the front matter date, else the file's creation time, else its modification
+        time (some file systems do not record creation times); --since/--until filter on it
+      - columns come from list.columns in config.toml or --columns
+        (path, title, created, modified, tags, words, size)
+
+    list / grep --format:
+      - json prints one array, jsonl one object per line, tsv tab-separated rows without a header
+      - list: path, title, tags, date (front matter), created, modified, size, words
+        (tsv columns in this order; created is empty where the file system has no birth time)
+      - grep: path, line, column, text and ranges ([start, end) byte offsets in the line,
+        start-end joined with , in tsv); --format always uses the builtin search, also with grep = "ripgrep"
+      - colors are disabled when stdout is not a terminal or NO_COLOR is set
+
+    cat:
+      - prints the file without rendering, so it can be piped (mmemo cat todo | wc -l)
+      - --no-front-matter (-b) prints only the body, --front-matter-json (--json) only the
+        front matter as a JSON object (one line per memo when piped)
+      - several memos can be selected; on a terminal each one gets a "==> path <==" header
 
     grep:
-      - grep = "builtin"      : simple AND search (all patterns must appear in the line)
+      - grep = "builtin"      : all patterns must appear in the line (AND)
+          OR / NOT            : "a b OR c NOT d" is (a and b) or (c and not d)
+          -e <regex>          : regular expression (also for patterns starting with '-')
+          -i / -s             : ignore case / case-sensitive (default: smart case,
+                                case-sensitive only when a pattern has uppercase letters)
+          -w, -v              : whole words, lines that do not match
+          -C/-A/-B <n>        : context lines around / after / before each match
       - grep = "ripgrep"      : pass arguments to "rg" as-is
-        (If a pattern starts with '-', use: mmemo grep -e "-foo")
+
+    tui:
+      - stays open until Esc (on an empty query) or Ctrl-C; type to filter the list
+      - Enter edit, Ctrl-O new, Ctrl-R rename/move (links are updated), Ctrl-T set front matter
+        tags, Ctrl-X or Delete move to trash, Ctrl-L reload, Ctrl-N/Ctrl-P move,
+        Ctrl-D/Ctrl-U scroll the preview
+      - the list is reloaded after the editor exits and every 2 seconds, so changes made
+        elsewhere show up
+
+    search:
+      - always uses the builtin selector; the input is searched like builtin grep arguments
+        (for example "-w todo OR fixme") and results are updated as you type
+      - Enter opens the editor at the line using editor_line_args (default: "+{line} {file}")
+
+    new:
+      - slug = "unicode" | "ascii" | "date-prefixed" | "timestamp" controls file names
+      - "/" in a title creates subdirectories; ".." and absolute paths are rejected
+      - an existing file gets a suffix (title-2.md)
+      - named templates live in ~/.config/mmemo/templates; pick one with --template <name>,
+        or from the selector when there are several (memo_template counts as one)
+      - templates support {{title}}, {{date}}, {{date:%Y-%m-%d %H:%M}}, {{time}}, {{uuid}},
+        {{env:NAME}}, variables from [template.vars] or --var name=value,
+        and {{#if name}}...{{else}}...{{/if}}
+      - the memo is written from memo_template (or a minimal front matter) before the editor
+        opens; remove_unchanged = true removes it again when nothing was edited
+
+    add / append:
+      - the text comes from -m (repeatable, joined by blank lines) or from stdin when piped
+      - add creates the memo from the template (--template or memo_template, no selector)
+        or appends the text as a new paragraph when the file already exists
+        (or a memo in the same directory has that file name or title)
+      - append adds "- YYYY-MM-DD HH:MM text" to the end of an existing memo
+
+    today / journal:
+      - entries are <memo_dir>/<journal.dir>/YYYY-MM-DD.md (journal.dir defaults to "journal")
+      - new entries use journal.template, else templates/journal.md, else the minimal
+        front matter; {{date}} is the entry's date
+      - journal.carry_over = true copies unchecked "- [ ]" items from the previous entry
+
+    mv:
+      - links to the memo in other memos are rewritten and listed ([[...]] keeps its form,
+        relative Markdown links get the new path); --no-update-links skips this
+      - the changes are shown and confirmed before other memos are written (skip with --yes)
+      - if a step fails, memos already rewritten are restored and the new file is removed
+
+    delete:
+      - memos are moved to <memo_dir>/.trash and can be brought back with "mmemo restore"
+      - asks for confirmation showing each memo's title and first lines (skip with --yes)
+      - without a terminal, --yes is required
+
+    tags:
+      - read from `tags:` in front matter and `#tag` in the memo body
+
+    index:
+      - off until "mmemo index rebuild" creates <memo_dir>/.mmemo/index.json;
+        delete the .mmemo directory to turn it off again
+      - stores titles, tags, dates and words of each memo; changed memos are re-read
+        (by modification time and size) whenever a command uses it
+      - used by list, tags, --tag filters, the selector's file list and builtin grep,
+        which only reads memos containing the pattern's words
 
     If an external command is not found, switch the corresponding setting to "builtin".
 
@@ -335,11 +2279,54 @@ EXAMPLES:
 
     mmemo edit                         # selector depends on config.toml
     mmemo view                         # selector/viewer depend on config.toml
+    mmemo edit my_memo                 # open directly if only one memo matches
+    mmemo cat my_memo | pbcopy         # print without rendering
+    mmemo cat --json my_memo | jq .tags
 
     mmemo grep todo                    # search "todo"
     mmemo grep foo bar                 # AND search (builtin): both "foo" and "bar"
+    mmemo grep todo OR fixme NOT done  # lines with "todo" or with "fixme" but not "done"
+    mmemo grep -w -C 2 -e 'v\d+'       # regex, whole words, 2 lines of context
+    mmemo grep --format jsonl todo     # machine-readable matches
+    mmemo list --format tsv | cut -f1,2
+    mmemo list --sort modified --reverse --limit 10
+    mmemo list work --since 2026-10-01 --columns path,title,tags
+    mmemo search todo                  # live search, open the editor at the chosen line
+    mmemo tui                          # keep open in a tmux pane
     mmemo grep -n todo                 # ripgrep only (grep = "ripgrep")
-    mmemo grep -e "-foo"               # ripgrep: pattern starting with '-'
+    mmemo grep -e "-foo"               # pattern starting with '-'
+
+    mmemo add inbox -m "call the dentist"
+    git log -1 --oneline | mmemo add work/log
+    mmemo append inbox -m "idea: tag cloud"
+
+    mmemo today
+    mmemo journal --yesterday
+    mmemo journal --date 2026-10-17
+
+    mmemo new --template meeting weekly sync
+    mmemo template new meeting
+
+    mmemo mv work/meeting notes        # rename the selected memo to work/meeting_notes.md
+    mmemo mv --from draft final        # rename draft.md to final.md
+    mmemo delete old_memo              # move to trash
+    mmemo delete --yes old_memo        # no confirmation (for scripts)
+    mmemo --dry-run delete old_memo    # only show what would be deleted
+    mmemo restore old_memo
+    mmemo trash empty --older-than 30d
+
+    mmemo index rebuild                # speed up list/grep on large memo directories
+    mmemo index status
+
+    mmemo links my_memo                # links and backlinks
+    mmemo view --follow my_memo        # view, then pick a link to view next
+    mmemo check --no-orphans           # e.g. in a pre-commit hook
+    mmemo graph | dot -Tsvg -o graph.svg
+    mmemo graph work --tags --format mermaid
+
+    mmemo tags
+    mmemo list --tag rust              # memos tagged "rust"
+    mmemo edit --tag rust --tag cli    # both tags
 
     mmemo config
     mmemo --help
@@ -352,3 +2339,218 @@ pub fn help() {
 pub fn version() {
     println!("mmemo {}", env!("CARGO_PKG_VERSION"));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    // テストごとのmemo_dir。filesは(path, 内容)
+    fn memo_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mmemo-commands-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn problems(dir: &Path, orphans: bool) -> Vec<String> {
+        let (_, problems) = find_problems(dir, orphans).unwrap();
+        problems
+            .iter()
+            .map(|p| format!("{}:{}: {}", p.file, p.line, p.message))
+            .collect()
+    }
+
+    fn graph_output(dir: &Path, format: GraphFormat, prefix: Option<&str>) -> String {
+        let graph = LinkGraph::load(dir).unwrap();
+        let options = GraphOptions {
+            tags: Vec::new(),
+            dir: None,
+            tag_nodes: true,
+            format,
+        };
+        let mut out = Vec::new();
+        write_graph(&mut out, &graph, &options, prefix).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // titleに"、\、[]、#、<>を含むmemo
+    const GRAPH_MEMOS: &[(&str, &str)] = &[
+        (
+            "a.md",
+            "---\ntitle: 'say \"hi\" [a] \\ #1 <b>'\ntags: [x]\n---\n[[b]]\n",
+        ),
+        ("b.md", "---\ntitle: B\n---\n[[a]] [[none]]\n"),
+        ("sub/c.md", "---\ntitle: C\n---\n[[a]]\n"),
+    ];
+
+    #[test]
+    fn graph_dot() {
+        let dir = memo_dir("graph-dot", GRAPH_MEMOS);
+        assert_eq!(
+            graph_output(&dir, GraphFormat::Dot, None),
+            r##"digraph mmemo {
+  "a.md" [label="say \"hi\" [a] \\ #1 <b>"];
+  "b.md" [label="B"];
+  "sub/c.md" [label="C"];
+  "tag:x" [label="#x", shape=box];
+  "a.md" -> "b.md";
+  "b.md" -> "a.md";
+  "sub/c.md" -> "a.md";
+  "a.md" -> "tag:x" [style=dashed, arrowhead=none];
+}
+"##
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn graph_json() {
+        let dir = memo_dir("graph-json", GRAPH_MEMOS);
+        let json: serde_json::Value =
+            serde_json::from_str(&graph_output(&dir, GraphFormat::Json, Some("sub/"))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "nodes": [{"id": "sub/c.md", "type": "memo", "title": "C"}],
+                "edges": [],
+            })
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&graph_output(&dir, GraphFormat::Json, None)).unwrap();
+        assert_eq!(json["nodes"][0]["title"], "say \"hi\" [a] \\ #1 <b>");
+        assert_eq!(json["nodes"][3]["id"], "tag:x");
+        assert_eq!(json["edges"].as_array().unwrap().len(), 4);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn graph_mermaid() {
+        let dir = memo_dir("graph-mermaid", GRAPH_MEMOS);
+        assert_eq!(
+            graph_output(&dir, GraphFormat::Mermaid, None),
+            r##"graph LR
+  n0["say #quot;hi#quot; [a] \ #35;1 #lt;b#gt;"]
+  n1["B"]
+  n2["C"]
+  t0(["#35;x"])
+  n0 --> n1
+  n1 --> n0
+  n2 --> n0
+  n0 -.- t0
+"##
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_links() {
+        let dir = memo_dir(
+            "check-links",
+            &[
+                (
+                    "a.md",
+                    "---\ntitle: A\n---\n[[B]] [[none]]\n[b](sub/b.md) [x](x.md) ![i](img.png)\n",
+                ),
+                (
+                    "sub/b.md",
+                    "---\ntitle: B\n---\n[a](../a.md) [[sub/none|n]]\n",
+                ),
+                ("img.png", ""),
+            ],
+        );
+
+        assert_eq!(
+            problems(&dir, false),
+            [
+                "a.md:4: broken link [[none]]",
+                "a.md:5: broken link (x.md)",
+                "sub/b.md:4: broken link [[sub/none]]",
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_front_matter_and_titles() {
+        let dir = memo_dir(
+            "check-memos",
+            &[
+                ("a.md", "---\ntitle: Same\n---\n"),
+                ("b.md", "---\ntitle: same\n---\n"),
+                ("c.md", "no front matter\n"),
+                ("d.md", "---\ntitle: D\n"),
+                ("e.md", "---\ntitle: E\n---\n[[Same]]\n"),
+            ],
+        );
+
+        let problems = problems(&dir, false);
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert_eq!(problems[0], "a.md:0: duplicate title \"Same\" (also b.md)");
+        assert_eq!(problems[1], "b.md:0: duplicate title \"same\" (also a.md)");
+        assert_eq!(problems[2], "c.md:0: missing front matter");
+        assert!(
+            problems[3].starts_with("d.md:0: malformed front matter: "),
+            "{}",
+            problems[3]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_orphans() {
+        let dir = memo_dir(
+            "check-orphans",
+            &[
+                ("a.md", "---\ntitle: A\n---\n[[B]] [[A]]\n"),
+                ("b.md", "---\ntitle: B\n---\n[[A]]\n"),
+                ("c.md", "---\ntitle: C\n---\n[[C]]\n"),
+                ("img.png", ""),
+            ],
+        );
+
+        // 自分自身へのリンクは数えない。添付ファイルはorphanにしない
+        assert!(problems(&dir, false).is_empty());
+        assert_eq!(
+            problems(&dir, true),
+            ["c.md:0: orphan (no links to this memo)"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_exit_status() {
+        let dir = memo_dir(
+            "check-status",
+            &[
+                ("a.md", "---\ntitle: A\n---\n[[B]]\n"),
+                ("b.md", "---\ntitle: B\n---\n[[A]] [[C]]\n"),
+            ],
+        );
+
+        let (memos, problems) = find_problems(&dir, false).unwrap();
+        assert_eq!(memos, 2);
+        assert!(matches!(
+            report_problems(memos, &problems),
+            Err(MmemoError::CheckFailed { problems: 1 })
+        ));
+
+        fs::write(dir.join("b.md"), "---\ntitle: B\n---\n[[A]]\n").unwrap();
+        let (memos, problems) = find_problems(&dir, false).unwrap();
+        assert!(report_problems(memos, &problems).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,8 +1,9 @@
 use std::{
+    collections::BTreeMap,
     fmt::Display,
     fs::{self, File},
     io::{Read, Write},
-    path::PathBuf,
+    path::{Component, PathBuf},
     str::FromStr,
 };
 
@@ -11,9 +12,15 @@ use toml::Table;
 use crate::app::{
     error::{MmemoError, MmemoResult},
     expand::HomeDir,
+    output::{self, DEFAULT_LIST_COLUMNS, ListColumn},
     path_utils::{config_dir, config_path, mmemo_dir, template_path},
+    slug::SlugKind,
+    template::DEFAULT_TEMPLATE,
 };
 
+// vim、neovim、emacs、nanoなど`+N file`で開けるエディタ向け
+pub const DEFAULT_EDITOR_LINE_ARGS: &str = "+{line} {file}";
+
 struct ConfigParser {
     contents: String,
 }
@@ -31,16 +38,20 @@ impl ConfigParser {
             message: "Please check the configuration settings.".to_string(),
         })?;
 
-        for (key, val) in &table {
+        for (key, val) in flatten("", &table) {
             let key: ConfigKey = key.parse().map_err(|_| MmemoError::Parse {
                 message: key.to_string(),
             })?;
-            let value = val
-                .as_str()
-                .ok_or(MmemoError::Parse {
-                    message: format!("{} must be a string", key),
-                })?
-                .to_string();
+            let value = match val {
+                toml::Value::String(s) => s.to_string(),
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Boolean(b) => b.to_string(),
+                _ => {
+                    return Err(MmemoError::Parse {
+                        message: format!("{} must be a string", key),
+                    });
+                }
+            };
 
             let token = Token { key, value };
             tokens.push(token);
@@ -50,6 +61,23 @@ impl ConfigParser {
     }
 }
 
+// `[template.vars]`のようなtableは`template.vars.name`というkeyにする
+fn flatten<'a>(prefix: &str, table: &'a Table) -> Vec<(String, &'a toml::Value)> {
+    let mut entries = Vec::new();
+    for (key, val) in table {
+        let key = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match val {
+            toml::Value::Table(table) => entries.extend(flatten(&key, table)),
+            val => entries.push((key, val)),
+        }
+    }
+    entries
+}
+
 #[derive(Debug)]
 struct Token {
     key: ConfigKey,
@@ -77,6 +105,48 @@ impl FromStr for SelectorKind {
     }
 }
 
+#[derive(Debug)]
+pub enum PreviewKind {
+    Markdown,
+    Raw,
+    None,
+}
+
+pub struct ParsePreviewKindError;
+
+impl FromStr for PreviewKind {
+    type Err = ParsePreviewKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" => Ok(PreviewKind::Markdown),
+            "raw" => Ok(PreviewKind::Raw),
+            "none" | "false" => Ok(PreviewKind::None),
+            _ => Err(ParsePreviewKindError),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PreviewPosition {
+    Right,
+    Bottom,
+}
+
+pub struct ParsePreviewPositionError;
+
+impl FromStr for PreviewPosition {
+    type Err = ParsePreviewPositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "right" => Ok(PreviewPosition::Right),
+            "bottom" => Ok(PreviewPosition::Bottom),
+            _ => Err(ParsePreviewPositionError),
+        }
+    }
+}
+
 pub enum InitStatus {
     Created,
     AlreadyInitialized,
@@ -85,11 +155,28 @@ pub enum InitStatus {
 #[derive(Debug)]
 pub struct Config {
     pub editor: String,
+    // 行を指定してエディタを開く時の引数。{file}と{line}を置き換える
+    pub editor_line_args: String,
     pub memo_dir: PathBuf,
     pub memo_template: Option<PathBuf>,
     pub selector: SelectorKind,
     pub viewer: ViewerKind,
     pub grep: GrepKind,
+    pub preview: PreviewKind,
+    pub preview_position: PreviewPosition,
+    // 画面に対するpreviewの割合（%）
+    pub preview_width: u16,
+    pub slug: SlugKind,
+    // newでエディタを閉じた時に何も変わっていなければ消す
+    pub remove_unchanged: bool,
+    // [template.vars]
+    pub template_vars: BTreeMap<String, String>,
+    // [journal] dirはmemo_dirからの相対パス
+    pub journal_dir: PathBuf,
+    pub journal_template: Option<PathBuf>,
+    pub journal_carry_over: bool,
+    // [list] columns
+    pub list_columns: Vec<ListColumn>,
 }
 
 #[derive(Debug)]
@@ -158,7 +245,8 @@ impl Config {
             "memo_dir = \"~/mmemo\"\n",
             "\n",
             "# Template file for new memos (optional)\n",
-            "# Supports {{title}} and {{date}} placeholders\n",
+            "# Supports {{title}}, {{date}}, {{date:%Y-%m-%d %H:%M}}, {{time}}, {{uuid}},\n",
+            "# {{env:NAME}}, [template.vars] and {{#if var}}...{{/if}}\n",
             "# Format: YAML front matter\n",
             "memo_template = \"~/.config/mmemo/template.txt\"\n",
             "\n",
@@ -169,7 +257,41 @@ impl Config {
             "viewer = \"builtin\"\n",
             "\n",
             "# Grep: builtin or ripgrep() (optional, default: builtin)\n",
-            "grep = \"builtin\"\n"
+            "grep = \"builtin\"\n",
+            "\n",
+            "# Preview in the builtin selector: markdown or raw or none (optional, default: markdown)\n",
+            "preview = \"markdown\"\n",
+            "\n",
+            "# Preview position: right or bottom (optional, default: right)\n",
+            "preview_position = \"right\"\n",
+            "\n",
+            "# Preview size in percent of the screen (optional, default: 50)\n",
+            "preview_width = 50\n",
+            "\n",
+            "# File names for new memos: unicode or ascii or date-prefixed or timestamp (optional, default: unicode)\n",
+            "slug = \"unicode\"\n",
+            "\n",
+            "# Remove a new memo when the editor exits without changes (optional, default: false)\n",
+            "remove_unchanged = false\n",
+            "\n",
+            "# Arguments to open the editor at a line, used by search (optional, default: \"+{line} {file}\")\n",
+            "# e.g. \"--goto {file}:{line}\" for VS Code, \"{file}:{line}\" for Helix\n",
+            "# editor_line_args = \"+{line} {file}\"\n",
+            "\n",
+            "# Variables for templates, used as {{name}} (optional)\n",
+            "# [template.vars]\n",
+            "# author = \"your name\"\n",
+            "\n",
+            "# Daily journal (optional)\n",
+            "# [journal]\n",
+            "# dir = \"journal\"                  # inside memo_dir\n",
+            "# template = \"~/.config/mmemo/templates/journal.md\"\n",
+            "# carry_over = true                # copy unchecked \"- [ ]\" items from the previous entry\n",
+            "\n",
+            "# Columns of `mmemo list` (optional, default: path,title,created)\n",
+            "# path, title, created, modified, tags, words, size\n",
+            "# [list]\n",
+            "# columns = \"path,title,created\"\n"
         );
 
         file.write_all(default.as_bytes())?;
@@ -179,15 +301,7 @@ impl Config {
     fn write_default_template() -> MmemoResult<()> {
         let mut file = File::create(template_path()?)?;
 
-        let default = concat!(
-            "---\n",
-            "title: {{title}}\n",
-            "date: {{date}}\n",
-            "---\n",
-            "\n",
-            "# {{title}}"
-        );
-        file.write_all(default.as_bytes())?;
+        file.write_all(DEFAULT_TEMPLATE.as_bytes())?;
 
         Ok(())
     }
@@ -232,6 +346,18 @@ impl TryFrom<Vec<Token>> for Config {
         let mut selector: Option<SelectorKind> = Some(SelectorKind::Builtin);
         let mut viewer: Option<ViewerKind> = Some(ViewerKind::Builtin);
         let mut grep: Option<GrepKind> = Some(GrepKind::Builtin);
+        let mut preview = PreviewKind::Markdown;
+        let mut preview_position = PreviewPosition::Right;
+        let mut preview_width: u16 = 50;
+        let mut slug = SlugKind::Unicode;
+        let mut remove_unchanged = false;
+        let mut editor_line_args = DEFAULT_EDITOR_LINE_ARGS.to_string();
+        let mut template_vars = BTreeMap::new();
+        let mut journal_dir = PathBuf::from("journal");
+        let mut journal_template: Option<PathBuf> = None;
+        let mut journal_carry_over = false;
+        let mut list_columns = DEFAULT_LIST_COLUMNS.to_vec();
+        let mut errors = Vec::new();
 
         for token in tokens {
             let value = token.value.trim();
@@ -256,9 +382,73 @@ impl TryFrom<Vec<Token>> for Config {
                         .and_then(|v| v.parse().ok())
                         .or(Some(GrepKind::Builtin))
                 }
+                ConfigKey::Preview => {
+                    preview = value
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(PreviewKind::Markdown)
+                }
+                ConfigKey::PreviewPosition => {
+                    preview_position = value
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(PreviewPosition::Right)
+                }
+                ConfigKey::Slug => match value.map(|v| v.parse::<SlugKind>()) {
+                    Some(Ok(kind)) => slug = kind,
+                    None => {}
+                    Some(Err(_)) => errors.push(
+                        "slug must be one of unicode, ascii, date-prefixed, timestamp".to_string(),
+                    ),
+                },
+                ConfigKey::RemoveUnchanged => match value.as_deref() {
+                    Some("true") => remove_unchanged = true,
+                    Some("false") | None => remove_unchanged = false,
+                    Some(_) => errors.push("remove_unchanged must be true or false".to_string()),
+                },
+                ConfigKey::EditorLineArgs => match value {
+                    Some(args) if args.contains("{file}") => editor_line_args = args,
+                    None => {}
+                    Some(_) => errors.push("editor_line_args must contain {file}".to_string()),
+                },
+                ConfigKey::TemplateVar(name) => {
+                    template_vars.insert(name, token.value);
+                }
+                ConfigKey::JournalDir => match value.map(PathBuf::from) {
+                    Some(dir)
+                        if dir.is_relative()
+                            && !dir.components().any(|c| c == Component::ParentDir) =>
+                    {
+                        journal_dir = dir
+                    }
+                    None => {}
+                    Some(_) => errors
+                        .push("journal.dir must be a relative path inside memo_dir".to_string()),
+                },
+                ConfigKey::JournalTemplate => journal_template = value.map(PathBuf::from),
+                ConfigKey::JournalCarryOver => match value.as_deref() {
+                    Some("true") => journal_carry_over = true,
+                    Some("false") | None => journal_carry_over = false,
+                    Some(_) => errors.push("journal.carry_over must be true or false".to_string()),
+                },
+                ConfigKey::ListColumns => match value.map(|v| output::parse_columns(&v)) {
+                    Some(Some(columns)) => list_columns = columns,
+                    None => {}
+                    Some(None) => errors.push(
+                        "list.columns must be a comma-separated list of path, title, created, modified, tags, words, size"
+                            .to_string(),
+                    ),
+                },
+                ConfigKey::PreviewWidth => match value.map(|v| v.parse::<u16>()) {
+                    Some(Ok(width)) if (10..=90).contains(&width) => preview_width = width,
+                    None => {}
+                    _ => errors.push("preview_width must be between 10 and 90".to_string()),
+                },
             }
         }
 
+        if !errors.is_empty() {
+            return Err(ConfigBuildError(errors));
+        }
+
         match (editor, memo_dir, memo_template, selector, viewer, grep) {
             (
                 Some(editor),
@@ -274,6 +464,17 @@ impl TryFrom<Vec<Token>> for Config {
                 selector,
                 viewer,
                 grep,
+                preview,
+                preview_position,
+                preview_width,
+                slug,
+                remove_unchanged,
+                editor_line_args,
+                template_vars,
+                journal_dir,
+                journal_template,
+                journal_carry_over,
+                list_columns,
             }),
             (_, d, _, _, _, _) => {
                 let vec = [(d.is_none(), "memo_dir")];
@@ -302,6 +503,17 @@ enum ConfigKey {
     Selector,
     Viewer,
     Grep,
+    Preview,
+    PreviewPosition,
+    PreviewWidth,
+    Slug,
+    RemoveUnchanged,
+    EditorLineArgs,
+    TemplateVar(String),
+    JournalDir,
+    JournalTemplate,
+    JournalCarryOver,
+    ListColumns,
 }
 
 impl Display for ConfigKey {
@@ -313,6 +525,17 @@ impl Display for ConfigKey {
             ConfigKey::Selector => write!(f, "selector"),
             ConfigKey::Viewer => write!(f, "viewer"),
             ConfigKey::Grep => write!(f, "grep"),
+            ConfigKey::Preview => write!(f, "preview"),
+            ConfigKey::PreviewPosition => write!(f, "preview_position"),
+            ConfigKey::PreviewWidth => write!(f, "preview_width"),
+            ConfigKey::Slug => write!(f, "slug"),
+            ConfigKey::RemoveUnchanged => write!(f, "remove_unchanged"),
+            ConfigKey::EditorLineArgs => write!(f, "editor_line_args"),
+            ConfigKey::TemplateVar(name) => write!(f, "template.vars.{}", name),
+            ConfigKey::JournalDir => write!(f, "journal.dir"),
+            ConfigKey::JournalTemplate => write!(f, "journal.template"),
+            ConfigKey::JournalCarryOver => write!(f, "journal.carry_over"),
+            ConfigKey::ListColumns => write!(f, "list.columns"),
         }
     }
 }
@@ -328,7 +551,20 @@ impl FromStr for ConfigKey {
             "selector" => Ok(ConfigKey::Selector),
             "viewer" => Ok(ConfigKey::Viewer),
             "grep" => Ok(ConfigKey::Grep),
-            _ => Err(ParseConfigKeyError),
+            "preview" => Ok(ConfigKey::Preview),
+            "preview_position" => Ok(ConfigKey::PreviewPosition),
+            "preview_width" => Ok(ConfigKey::PreviewWidth),
+            "slug" => Ok(ConfigKey::Slug),
+            "remove_unchanged" => Ok(ConfigKey::RemoveUnchanged),
+            "editor_line_args" => Ok(ConfigKey::EditorLineArgs),
+            "journal.dir" => Ok(ConfigKey::JournalDir),
+            "journal.template" => Ok(ConfigKey::JournalTemplate),
+            "journal.carry_over" => Ok(ConfigKey::JournalCarryOver),
+            "list.columns" => Ok(ConfigKey::ListColumns),
+            _ => match s.strip_prefix("template.vars.") {
+                Some(name) if !name.is_empty() => Ok(ConfigKey::TemplateVar(name.to_string())),
+                _ => Err(ParseConfigKeyError),
+            },
         }
     }
 }
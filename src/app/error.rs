@@ -25,6 +25,21 @@ pub enum MmemoError {
 
     #[error("Invalid arguments: {message}")]
     InvalidArgs { message: String },
+
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+
+    #[error("No memo matches: {query}")]
+    MemoNotFound { query: String },
+
+    #[error("Multiple memos match {query}:\n  {}", .candidates.join("\n  "))]
+    AmbiguousMemo {
+        query: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("{problems} problem(s) found")]
+    CheckFailed { problems: usize },
 }
 
 pub type MmemoResult<T> = std::result::Result<T, MmemoError>;
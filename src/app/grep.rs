@@ -0,0 +1,433 @@
+use std::{fs, path::Path};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::app::{
+    error::{MmemoError, MmemoResult},
+    index::{self, TokenMatch},
+    selector::core::{MatchResult, Source},
+};
+
+// builtin grepの検索条件。`a b OR c NOT d`は(a AND b) OR (c AND NOT d)
+pub struct GrepQuery {
+    groups: Vec<Vec<Term>>,
+    // -v
+    invert: bool,
+    // -w
+    word: bool,
+    // -B/-A（-Cは両方）
+    pub before: usize,
+    pub after: usize,
+}
+
+struct Term {
+    regex: Regex,
+    negated: bool,
+    // -eでないpattern。indexで候補を絞るのに使う
+    literal: Option<String>,
+}
+
+// (pattern, -eで渡されたか, NOTか)
+type RawTerm = (String, bool, bool);
+
+impl GrepQuery {
+    // `-i -w -e <regex> -v -C/-A/-B <n>`とpattern、`OR`、`NOT`
+    pub fn parse(args: &[String]) -> MmemoResult<Self> {
+        let invalid = |message: String| MmemoError::InvalidArgs { message };
+
+        let mut ignore_case: Option<bool> = None;
+        let mut word = false;
+        let mut invert = false;
+        let mut before = 0;
+        let mut after = 0;
+
+        let mut groups: Vec<Vec<RawTerm>> = Vec::new();
+        let mut group: Vec<RawTerm> = Vec::new();
+        let mut negate = false;
+        let mut only_patterns = false;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let arg = arg.as_str();
+            if only_patterns || !arg.starts_with('-') || arg == "-" {
+                match arg {
+                    "OR" if !only_patterns => {
+                        if group.is_empty() || negate {
+                            return Err(invalid("OR needs a pattern on both sides".to_string()));
+                        }
+                        groups.push(std::mem::take(&mut group));
+                    }
+                    "NOT" if !only_patterns => negate = true,
+                    _ => {
+                        group.push((arg.to_string(), false, negate));
+                        negate = false;
+                    }
+                }
+                continue;
+            }
+
+            let mut number = |name: &str, attached: &str| -> MmemoResult<usize> {
+                let value = match attached {
+                    "" => args.next().map(String::as_str).unwrap_or_default(),
+                    attached => attached,
+                };
+                value
+                    .parse()
+                    .map_err(|_| invalid(format!("{} needs a number: {}", name, value)))
+            };
+
+            match arg {
+                "--" => only_patterns = true,
+                "-e" | "--regexp" => {
+                    let Some(pattern) = args.next() else {
+                        return Err(invalid("-e needs a pattern".to_string()));
+                    };
+                    group.push((pattern.clone(), true, negate));
+                    negate = false;
+                }
+                "--ignore-case" => ignore_case = Some(true),
+                "--case-sensitive" => ignore_case = Some(false),
+                "--word-regexp" => word = true,
+                "--invert-match" => invert = true,
+                _ if arg.starts_with("-C") => {
+                    let n = number("-C", &arg[2..])?;
+                    before = n;
+                    after = n;
+                }
+                _ if arg.starts_with("-A") => after = number("-A", &arg[2..])?,
+                _ if arg.starts_with("-B") => before = number("-B", &arg[2..])?,
+                // `-iw`のようにまとめて書ける
+                _ if arg[1..].chars().all(|c| "iswv".contains(c)) => {
+                    for c in arg[1..].chars() {
+                        match c {
+                            'i' => ignore_case = Some(true),
+                            's' => ignore_case = Some(false),
+                            'w' => word = true,
+                            _ => invert = true,
+                        }
+                    }
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown grep option: {} (set grep = \"ripgrep\" for ripgrep options)",
+                        arg
+                    )));
+                }
+            }
+        }
+
+        if negate {
+            return Err(invalid("NOT needs a pattern".to_string()));
+        }
+        if group.is_empty() {
+            return Err(invalid(match groups.is_empty() {
+                true => "no pattern".to_string(),
+                false => "OR needs a pattern on both sides".to_string(),
+            }));
+        }
+        groups.push(group);
+
+        let raw = groups.iter().flatten();
+        if raw.clone().any(|(pattern, _, _)| pattern.is_empty()) {
+            return Err(invalid("empty pattern".to_string()));
+        }
+        // smart case: 大文字を含まなければ大文字小文字を区別しない
+        let ignore_case = ignore_case.unwrap_or_else(|| {
+            !raw.clone()
+                .any(|(pattern, is_regex, _)| has_upper(pattern, *is_regex))
+        });
+
+        let groups = groups
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_iter()
+                    .map(|(pattern, is_regex, negated)| {
+                        let mut source = if is_regex {
+                            pattern.clone()
+                        } else {
+                            regex::escape(&pattern)
+                        };
+                        if word {
+                            source = format!(r"\b(?:{})\b", source);
+                        }
+                        let regex = RegexBuilder::new(&source)
+                            .case_insensitive(ignore_case)
+                            .build()
+                            .map_err(|e| invalid(format!("invalid regex {}: {}", pattern, e)))?;
+                        Ok(Term {
+                            regex,
+                            negated,
+                            literal: (!is_regex).then_some(pattern),
+                        })
+                    })
+                    .collect::<MmemoResult<Vec<_>>>()
+            })
+            .collect::<MmemoResult<Vec<_>>>()?;
+
+        Ok(GrepQuery {
+            groups,
+            invert,
+            word,
+            before,
+            after,
+        })
+    }
+
+    // 一致する行を含むmemoが必ず持っている単語（ORのまとまりごと）。絞れない時はNone
+    pub fn required_words(&self) -> Option<Vec<Vec<(String, TokenMatch)>>> {
+        if self.invert {
+            return None;
+        }
+        self.groups
+            .iter()
+            .map(|group| {
+                let words: Vec<(String, TokenMatch)> = group
+                    .iter()
+                    .filter(|term| !term.negated)
+                    .filter_map(|term| term.literal.as_deref())
+                    .flat_map(|pattern| index::query_tokens(pattern, self.word))
+                    .collect();
+                (!words.is_empty()).then_some(words)
+            })
+            .collect()
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        let matched = self.groups.iter().any(|group| {
+            group
+                .iter()
+                .all(|term| term.regex.is_match(line) != term.negated)
+        });
+        matched != self.invert
+    }
+
+    // NOTでないpatternが当たった位置（byte offset）。重なりはマージして位置順。-vの時は空
+    pub fn ranges(&self, line: &str) -> Vec<(usize, usize)> {
+        if self.invert {
+            return Vec::new();
+        }
+
+        let mut ranges: Vec<(usize, usize)> = self
+            .groups
+            .iter()
+            .flatten()
+            .filter(|term| !term.negated)
+            .flat_map(|term| term.regex.find_iter(line))
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect();
+
+        // 位置順に並べて、重なりはマージ（“赤くする領域の和集合”にする）
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (s, e) in ranges {
+            match merged.last_mut() {
+                Some((_, le)) if s <= *le => *le = (*le).max(e),
+                _ => merged.push((s, e)),
+            }
+        }
+        merged
+    }
+}
+
+// regexの`\W`や`\S`のようなエスケープは大文字に数えない
+fn has_upper(pattern: &str, is_regex: bool) -> bool {
+    let mut escaped = false;
+    for c in pattern.chars() {
+        if is_regex && escaped {
+            escaped = false;
+            continue;
+        }
+        if is_regex && c == '\\' {
+            escaped = true;
+            continue;
+        }
+        if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+// `mmemo search`の候補。itemは`path:line:text`
+pub struct LiveGrep {
+    // (memo_dirからの相対パス, 行)
+    files: Vec<(String, Vec<String>)>,
+}
+
+// これより多い時は打ち切る（描画とpreviewが重くなるので）
+const LIVE_GREP_LIMIT: usize = 10_000;
+
+impl LiveGrep {
+    pub fn load(memo_dir: &Path, files: Vec<String>) -> MmemoResult<Self> {
+        let mut loaded = Vec::new();
+        for file in files {
+            // バイナリなどUTF-8でないファイルは飛ばす
+            let Ok(content) = fs::read_to_string(memo_dir.join(&file)) else {
+                continue;
+            };
+            loaded.push((file, content.lines().map(str::to_string).collect()));
+        }
+        Ok(LiveGrep { files: loaded })
+    }
+}
+
+// LiveGrepのitemからpathと行番号を取り出す。pathに`:`があっても長い方を優先する
+pub fn locate(files: &[String], item: &str) -> Option<(String, usize)> {
+    files
+        .iter()
+        .filter_map(|path| {
+            let rest = item.strip_prefix(path.as_str())?.strip_prefix(':')?;
+            let (line, _) = rest.split_once(':')?;
+            Some((path.clone(), line.parse().ok()?))
+        })
+        .max_by_key(|(path, _)| path.len())
+}
+
+impl Source for LiveGrep {
+    fn total(&self) -> usize {
+        self.files.iter().map(|(_, lines)| lines.len()).sum()
+    }
+
+    // 入力はgrepの引数と同じように空白で区切る（`-w foo OR bar`など）
+    fn search(&mut self, input: &str) -> Vec<MatchResult> {
+        let args: Vec<String> = input.split_whitespace().map(str::to_string).collect();
+        if args.is_empty() {
+            return Vec::new();
+        }
+        // 入力途中の不完全なregexなどは何も出さない
+        let Ok(query) = GrepQuery::parse(&args) else {
+            return Vec::new();
+        };
+
+        let mut results = Vec::new();
+        for (path, lines) in &self.files {
+            for (i, line) in lines.iter().enumerate() {
+                if !query.is_match(line) {
+                    continue;
+                }
+                let prefix = format!("{}:{}:", path, i + 1);
+                let hits = query
+                    .ranges(line)
+                    .into_iter()
+                    .flat_map(|(s, e)| line[s..e].char_indices().map(move |(c, _)| s + c))
+                    .map(|offset| prefix.len() + offset)
+                    .collect();
+                results.push(MatchResult::new(
+                    format!("{}{}", prefix, line),
+                    hits,
+                    Some((path.clone(), i + 1)),
+                ));
+                if results.len() >= LIVE_GREP_LIMIT {
+                    return results;
+                }
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(args: &str) -> MmemoResult<GrepQuery> {
+        let args: Vec<String> = args.split(' ').map(str::to_string).collect();
+        GrepQuery::parse(&args)
+    }
+
+    fn matches(args: &str, line: &str) -> bool {
+        query(args).unwrap().is_match(line)
+    }
+
+    #[test]
+    fn and_or_not() {
+        assert!(matches("foo bar", "bar and foo"));
+        assert!(!matches("foo bar", "foo only"));
+        assert!(matches("foo OR bar", "bar only"));
+        assert!(matches("foo NOT bar", "foo only"));
+        assert!(!matches("foo NOT bar", "foo and bar"));
+        // (a AND b) OR (c AND NOT d)
+        assert!(matches("a b OR c NOT d", "c"));
+        assert!(!matches("a b OR c NOT d", "c d"));
+        assert!(matches("a b OR c NOT d", "a b d"));
+    }
+
+    #[test]
+    fn smart_case() {
+        assert!(matches("todo", "TODO"));
+        assert!(!matches("Todo", "TODO"));
+        assert!(matches("-i Todo", "TODO"));
+        assert!(!matches("-s todo", "TODO"));
+        // `\W`は大文字に数えない
+        assert!(matches(r"-e todo\W", "TODO:"));
+    }
+
+    #[test]
+    fn options() {
+        assert!(!matches("-w foo", "foobar"));
+        assert!(matches("-w foo", "a foo b"));
+        assert!(matches("-v foo", "bar"));
+        assert!(matches("-iwv foo", "foobar"));
+        assert!(matches("-e a.c", "abc"));
+        assert!(!matches("a.c", "abc"));
+        assert!(matches("-- -v", "x -v y"));
+        assert!(matches("-- OR", "OR"));
+
+        let q = query("-C 2 foo").unwrap();
+        assert_eq!((q.before, q.after), (2, 2));
+        let q = query("-B1 -A3 foo").unwrap();
+        assert_eq!((q.before, q.after), (1, 3));
+    }
+
+    #[test]
+    fn errors() {
+        for args in [
+            "OR foo",
+            "foo OR",
+            "foo NOT",
+            "NOT OR foo",
+            "-e",
+            "-C x foo",
+            "-x foo",
+            "-e ( foo",
+            "-i",
+        ] {
+            assert!(query(args).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn ranges_merge_and_skip_negated() {
+        let q = query("ab bc NOT x").unwrap();
+        assert_eq!(q.ranges("abc abc"), [(0, 3), (4, 7)]);
+        assert!(query("-v ab").unwrap().ranges("ab").is_empty());
+    }
+
+    #[test]
+    fn required_words() {
+        use TokenMatch::*;
+        let words = query("foo bar OR baz NOT qux").unwrap().required_words();
+        assert_eq!(
+            words,
+            Some(vec![
+                vec![
+                    ("foo".to_string(), Substring),
+                    ("bar".to_string(), Substring)
+                ],
+                vec![("baz".to_string(), Substring)]
+            ])
+        );
+        let words = query("-w Foo-bar").unwrap().required_words();
+        assert_eq!(
+            words,
+            Some(vec![vec![
+                ("foo".to_string(), Exact),
+                ("bar".to_string(), Exact)
+            ]])
+        );
+        assert_eq!(query("foo OR -e b.r").unwrap().required_words(), None);
+        assert_eq!(query("-v foo").unwrap().required_words(), None);
+    }
+}
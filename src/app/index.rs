@@ -0,0 +1,476 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, ErrorKind},
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{commands::dir_files, error::MmemoResult, memo::Memo};
+
+// memo_dir直下。dotで始まるのでdir_filesには出てこない
+const INDEX_DIR: &str = ".mmemo";
+const INDEX_FILE: &str = "index.json";
+// 形式を変えたら上げる。違えば作り直す
+const VERSION: u32 = 1;
+
+// --dry-runの時は読み直した結果を保存しない
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    // UNIX epochからのナノ秒。sizeと合わせて変更を調べる
+    pub modified: u64,
+    pub created: Option<u64>,
+    pub size: u64,
+    pub title: String,
+    // front matterのdate（YYYY-MM-DD）
+    pub date: Option<String>,
+    pub tags: Vec<String>,
+    pub words: usize,
+    // 小文字にした単語（英数字の並び）
+    tokens: Vec<String>,
+}
+
+impl IndexEntry {
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter()
+            .all(|tag| self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+}
+
+pub struct Index {
+    entries: BTreeMap<String, IndexEntry>,
+    // 単語 -> その単語を含むmemo（転置インデックス）
+    postings: BTreeMap<String, BTreeSet<String>>,
+}
+
+// 検索語と語彙の単語の当て方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenMatch {
+    // 両側が単語の切れ目
+    Exact,
+    // 左側だけ切れ目。単語の先頭から始まる
+    Prefix,
+    // 単語の途中から始まるかもしれない
+    Substring,
+}
+
+#[derive(Debug, Default)]
+pub struct UpdateStats {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl UpdateStats {
+    fn changed(&self) -> bool {
+        self.added + self.updated + self.removed > 0
+    }
+}
+
+pub fn set_read_only() {
+    READ_ONLY.store(true, Ordering::Relaxed);
+}
+
+pub fn index_path(memo_dir: &Path) -> PathBuf {
+    memo_dir.join(INDEX_DIR).join(INDEX_FILE)
+}
+
+// indexがあれば変わったmemoだけ読み直して返す。`mmemo index rebuild`するまではNone
+pub fn open(memo_dir: &Path) -> MmemoResult<Option<Index>> {
+    let Some(entries) = read(memo_dir)? else {
+        return Ok(None);
+    };
+    let (entries, stats) = update(memo_dir, entries)?;
+    if stats.changed() && !READ_ONLY.load(Ordering::Relaxed) {
+        write(memo_dir, &entries)?;
+    }
+    Ok(Some(Index::new(entries)))
+}
+
+// 全部読み直す。saveがfalseなら保存しない（--dry-run）
+pub fn rebuild(memo_dir: &Path, save: bool) -> MmemoResult<Index> {
+    let (entries, _) = update(memo_dir, BTreeMap::new())?;
+    if save {
+        write(memo_dir, &entries)?;
+    }
+    Ok(Index::new(entries))
+}
+
+// 保存されたまま（更新しない）のindexと、今のファイルとの差分
+pub fn status(memo_dir: &Path) -> MmemoResult<Option<(Index, UpdateStats)>> {
+    let Some(entries) = read(memo_dir)? else {
+        return Ok(None);
+    };
+    let (_, stats) = update(memo_dir, entries.clone())?;
+    Ok(Some((Index::new(entries), stats)))
+}
+
+fn read(memo_dir: &Path) -> MmemoResult<Option<BTreeMap<String, IndexEntry>>> {
+    let content = match fs::read_to_string(index_path(memo_dir)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // 壊れていたり古い形式なら空から作り直す
+    match serde_json::from_str::<IndexFile>(&content) {
+        Ok(file) if file.version == VERSION => Ok(Some(file.entries)),
+        _ => Ok(Some(BTreeMap::new())),
+    }
+}
+
+fn write(memo_dir: &Path, entries: &BTreeMap<String, IndexEntry>) -> MmemoResult<()> {
+    let path = index_path(memo_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = IndexFile {
+        version: VERSION,
+        entries: entries.clone(),
+    };
+    let json = serde_json::to_string(&file).map_err(io::Error::other)?;
+
+    // 途中で止まっても壊れないように別名で書いてから置き換える
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+// mtimeかsizeが変わったmemoだけ読み直す
+fn update(
+    memo_dir: &Path,
+    mut entries: BTreeMap<String, IndexEntry>,
+) -> MmemoResult<(BTreeMap<String, IndexEntry>, UpdateStats)> {
+    let mut stats = UpdateStats::default();
+    let files: BTreeSet<String> = dir_files(memo_dir)?.into_iter().collect();
+
+    let before = entries.len();
+    entries.retain(|path, _| files.contains(path));
+    stats.removed = before - entries.len();
+
+    for file in files {
+        let metadata = fs::metadata(memo_dir.join(&file))?;
+        let modified = nanos(metadata.modified().ok());
+        let unchanged = entries
+            .get(&file)
+            .is_some_and(|e| e.modified == modified && e.size == metadata.len());
+        if unchanged {
+            continue;
+        }
+
+        // UTF-8でないファイルは空として扱う
+        let content = fs::read_to_string(memo_dir.join(&file)).unwrap_or_default();
+        let memo = Memo::parse(&file, &content).ok();
+        let body = memo.as_ref().map(|m| m.body.as_str()).unwrap_or(&content);
+        let entry = IndexEntry {
+            modified,
+            created: metadata.created().ok().map(|t| nanos(Some(t))),
+            size: metadata.len(),
+            title: match &memo {
+                Some(memo) => memo.title(),
+                None => Path::new(&file)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            },
+            date: memo.as_ref().and_then(|m| m.date()).map(|d| d.to_string()),
+            tags: memo.as_ref().map(|m| m.tags()).unwrap_or_default(),
+            words: body.split_whitespace().count(),
+            tokens: tokens(&content).into_iter().collect(),
+        };
+
+        match entries.insert(file, entry) {
+            Some(_) => stats.updated += 1,
+            None => stats.added += 1,
+        }
+    }
+
+    Ok((entries, stats))
+}
+
+fn nanos(time: Option<SystemTime>) -> u64 {
+    time.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// 英数字（日本語なども含む）の並びを小文字にしたもの
+pub fn tokens(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// grepのpatternを小文字の単語に分け、語彙への当て方を付ける。
+// 前後に別の単語がある（間に記号がある）側は単語の切れ目になる。wordは-w
+pub fn query_tokens(pattern: &str, word: bool) -> Vec<(String, TokenMatch)> {
+    let parts: Vec<&str> = pattern.split(|c: char| !c.is_alphanumeric()).collect();
+    let last = parts.len() - 1;
+    parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| !part.is_empty())
+        .map(|(i, part)| {
+            let kind = match (word || i > 0, word || i < last) {
+                (true, true) => TokenMatch::Exact,
+                (true, false) => TokenMatch::Prefix,
+                (false, _) => TokenMatch::Substring,
+            };
+            (part.to_lowercase(), kind)
+        })
+        .collect()
+}
+
+impl Index {
+    fn new(entries: BTreeMap<String, IndexEntry>) -> Self {
+        let mut postings: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (path, entry) in &entries {
+            for token in &entry.tokens {
+                postings
+                    .entry(token.clone())
+                    .or_default()
+                    .insert(path.clone());
+            }
+        }
+        Index { entries, postings }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn terms(&self) -> usize {
+        self.postings.len()
+    }
+
+    pub fn get(&self, path: &str) -> Option<&IndexEntry> {
+        self.entries.get(path)
+    }
+
+    // tagsを全部持つmemo（path順）
+    pub fn files_with_tags(&self, tags: &[String]) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.has_tags(tags))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    // groupsのどれかについて、全ての単語を含むmemoだけに絞る
+    pub fn candidates(
+        &self,
+        files: Vec<String>,
+        groups: &[Vec<(String, TokenMatch)>],
+    ) -> Vec<String> {
+        let mut matched: BTreeSet<&str> = BTreeSet::new();
+        for group in groups {
+            let mut group_files: Option<BTreeSet<&str>> = None;
+            for (word, kind) in group {
+                let files: BTreeSet<&str> = self
+                    .matching_tokens(word, *kind)
+                    .flat_map(|paths| paths.iter().map(String::as_str))
+                    .collect();
+                group_files = Some(match group_files {
+                    Some(prev) => prev.intersection(&files).copied().collect(),
+                    None => files,
+                });
+            }
+            matched.extend(group_files.unwrap_or_default());
+        }
+
+        files
+            .into_iter()
+            .filter(|file| matched.contains(file.as_str()))
+            .collect()
+    }
+
+    // wordに当たる語彙のpostings。単語の途中から始まる時だけ語彙を全部見る
+    fn matching_tokens<'a>(
+        &'a self,
+        word: &'a str,
+        kind: TokenMatch,
+    ) -> Box<dyn Iterator<Item = &'a BTreeSet<String>> + 'a> {
+        match kind {
+            TokenMatch::Exact => Box::new(self.postings.get(word).into_iter()),
+            TokenMatch::Prefix => Box::new(
+                self.postings
+                    .range::<str, _>((Bound::Included(word), Bound::Unbounded))
+                    .take_while(move |(token, _)| token.starts_with(word))
+                    .map(|(_, paths)| paths),
+            ),
+            TokenMatch::Substring => Box::new(
+                self.postings
+                    .iter()
+                    .filter(move |(token, _)| token.contains(word))
+                    .map(|(_, paths)| paths),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs::File, process, time::Duration};
+
+    use super::*;
+
+    fn memo_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mmemo-index-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn words(list: &[(&str, TokenMatch)]) -> Vec<(String, TokenMatch)> {
+        list.iter().map(|(w, k)| (w.to_string(), *k)).collect()
+    }
+
+    #[test]
+    fn update_detects_changes() {
+        let dir = memo_dir(
+            "update",
+            &[
+                ("a.md", "---\ntitle: A\ntags: [x]\n---\nhello world\n"),
+                ("b.md", "bbbb\n"),
+                ("sub/c.md", "c\n"),
+            ],
+        );
+        let (entries, stats) = update(&dir, BTreeMap::new()).unwrap();
+        assert_eq!((stats.added, stats.updated, stats.removed), (3, 0, 0));
+        assert_eq!(entries["a.md"].title, "A");
+        assert_eq!(entries["a.md"].tags, ["x"]);
+        assert_eq!(entries["a.md"].words, 2);
+
+        let (entries, stats) = update(&dir, entries).unwrap();
+        assert!(!stats.changed());
+
+        // 同じsizeでもmtimeが変われば読み直す
+        fs::write(dir.join("b.md"), "cccc\n").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(dir.join("b.md"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        fs::write(dir.join("sub/c.md"), "longer\n").unwrap();
+        fs::remove_file(dir.join("a.md")).unwrap();
+
+        let (entries, stats) = update(&dir, entries).unwrap();
+        assert_eq!((stats.added, stats.updated, stats.removed), (0, 2, 1));
+        assert!(!entries.contains_key("a.md"));
+        assert_eq!(entries["b.md"].tokens, ["cccc"]);
+        assert_eq!(entries["sub/c.md"].tokens, ["longer"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let dir = memo_dir(
+            "json",
+            &[("a.md", "---\ntitle: A\ndate: 2026-10-18\n---\nx\n")],
+        );
+        assert!(read(&dir).unwrap().is_none());
+        let built = rebuild(&dir, true).unwrap();
+
+        let entries = read(&dir).unwrap().unwrap();
+        let entry = &entries["a.md"];
+        assert_eq!(entry.date(), NaiveDate::from_ymd_opt(2026, 10, 18));
+        assert_eq!(entry.size, built.get("a.md").unwrap().size);
+        assert_eq!(entry.tokens, built.get("a.md").unwrap().tokens);
+
+        // 壊れたindexは空として読み、次のopenで作り直す
+        fs::write(index_path(&dir), "{").unwrap();
+        assert!(read(&dir).unwrap().unwrap().is_empty());
+        assert_eq!(open(&dir).unwrap().unwrap().len(), 1);
+        assert_eq!(read(&dir).unwrap().unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rebuild_without_save() {
+        let dir = memo_dir("nosave", &[("a.md", "x\n")]);
+        assert_eq!(rebuild(&dir, false).unwrap().len(), 1);
+        assert!(!index_path(&dir).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn query_token_kinds() {
+        use TokenMatch::*;
+        assert_eq!(query_tokens("foo", false), words(&[("foo", Substring)]));
+        assert_eq!(query_tokens("foo", true), words(&[("foo", Exact)]));
+        assert_eq!(
+            query_tokens("Release notes v2", false),
+            words(&[("release", Substring), ("notes", Exact), ("v2", Prefix)])
+        );
+        assert_eq!(query_tokens("-foo", false), words(&[("foo", Prefix)]));
+        assert_eq!(query_tokens("foo-", false), words(&[("foo", Substring)]));
+        assert!(query_tokens("--", false).is_empty());
+    }
+
+    #[test]
+    fn candidates() {
+        use TokenMatch::*;
+        let dir = memo_dir(
+            "candidates",
+            &[
+                ("a.md", "release notes\n"),
+                ("b.md", "releases and notebook\n"),
+                ("c.md", "prerelease\n"),
+            ],
+        );
+        let index = rebuild(&dir, false).unwrap();
+        let all = || vec!["a.md".to_string(), "b.md".into(), "c.md".into()];
+
+        let find = |groups: &[&[(&str, TokenMatch)]]| {
+            let groups: Vec<_> = groups.iter().map(|g| words(g)).collect();
+            index.candidates(all(), &groups)
+        };
+        assert_eq!(find(&[&[("release", Exact)]]), ["a.md"]);
+        assert_eq!(find(&[&[("release", Prefix)]]), ["a.md", "b.md"]);
+        assert_eq!(find(&[&[("release", Substring)]]), ["a.md", "b.md", "c.md"]);
+        assert_eq!(
+            find(&[&[("release", Prefix), ("note", Prefix)]]),
+            ["a.md", "b.md"]
+        );
+        assert_eq!(
+            find(&[&[("notes", Exact)], &[("prerelease", Exact)]]),
+            ["a.md", "c.md"]
+        );
+        assert!(find(&[&[("missing", Substring)]]).is_empty());
+        // 渡したfilesの中だけ
+        assert_eq!(
+            index.candidates(vec!["b.md".into()], &[words(&[("note", Prefix)])]),
+            ["b.md"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
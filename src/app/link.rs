@@ -0,0 +1,691 @@
+use std::{collections::BTreeMap, fs, ops::Range, path::Path, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::app::{commands::dir_files, error::MmemoResult, memo::Memo};
+
+// `[[target]]`、`[[target|別名]]`、`[[target#見出し]]`
+static WIKI_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[([^\[\]\n]+?)\]\]").unwrap());
+// `[text](href)`、`[text](href "title")`。画像（`![`）も拾う
+static MARKDOWN_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\[[^\[\]\n]*\]\(([^()\s]+)(?:\s+"[^"\n]*")?\)"#).unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    Wiki,
+    // memo_dir内への相対リンク
+    Markdown,
+}
+
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub kind: LinkKind,
+    // 見出し（#...）と別名（|...）を除いたもの。Markdownはパーセントデコード済み
+    pub target: String,
+    // 1始まりの行番号（front matterも数える）
+    pub line: usize,
+    // ファイル内でtargetが書かれている位置（byte）。Markdownはデコード前
+    pub span: Range<usize>,
+    // 指しているmemo。見つからなければNone
+    pub resolved: Option<String>,
+}
+
+impl Link {
+    // 書かれた形に近い表示
+    pub fn label(&self) -> String {
+        match self.kind {
+            LinkKind::Wiki => format!("[[{}]]", self.target),
+            LinkKind::Markdown => format!("({})", self.target),
+        }
+    }
+}
+
+// memoの移動に合わせてリンクを書き換えた内容
+pub struct Rewrite {
+    // 書き換えるmemo（移動前のpath）
+    pub file: String,
+    pub content: String,
+    // (行, 前, 後)
+    pub changes: Vec<(usize, String, String)>,
+}
+
+// memo_dir全体のリンク
+pub struct LinkGraph {
+    // path -> title
+    titles: BTreeMap<String, String>,
+    // path -> tags（front matterと本文の`#tag`）
+    tags: BTreeMap<String, Vec<String>>,
+    // path -> そのmemoから出ているリンク（書かれた順）
+    links: BTreeMap<String, Vec<Link>>,
+    // path -> 画像など、memoを指さない相対リンク。memoを移動した時に直すだけで数えない
+    attachments: BTreeMap<String, Vec<Link>>,
+}
+
+impl LinkGraph {
+    pub fn load(memo_dir: &Path) -> MmemoResult<Self> {
+        let mut titles = BTreeMap::new();
+        let mut tags = BTreeMap::new();
+        let mut contents = Vec::new();
+        for file in dir_files(memo_dir)? {
+            // UTF-8でないファイルはリンクを持たないものとして扱う
+            let content = fs::read_to_string(memo_dir.join(&file)).unwrap_or_default();
+            let (title, memo_tags) = match Memo::parse(&file, &content) {
+                Ok(memo) => (memo.title(), memo.tags()),
+                Err(_) => (stem(&file).to_string(), Vec::new()),
+            };
+            titles.insert(file.clone(), title);
+            tags.insert(file.clone(), memo_tags);
+            contents.push((file, content));
+        }
+
+        let mut graph = LinkGraph {
+            titles,
+            tags,
+            links: BTreeMap::new(),
+            attachments: BTreeMap::new(),
+        };
+        for (file, content) in contents {
+            let (found, mut attachments) = extract(&content);
+            let mut links = Vec::new();
+            for mut link in found {
+                link.resolved = graph.resolve(&file, link.kind, &link.target);
+                // 拡張子が.mdでなく、memoでもない相対リンクは数えない
+                if link.kind == LinkKind::Markdown
+                    && link.resolved.is_none()
+                    && !link.target.to_lowercase().ends_with(".md")
+                {
+                    attachments.push(link);
+                } else {
+                    links.push(link);
+                }
+            }
+            attachments.sort_by_key(|link| link.span.start);
+            graph.links.insert(file.clone(), links);
+            graph.attachments.insert(file, attachments);
+        }
+        Ok(graph)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &String> {
+        self.titles.keys()
+    }
+
+    pub fn title(&self, path: &str) -> Option<&str> {
+        self.titles.get(path).map(String::as_str)
+    }
+
+    pub fn tags(&self, path: &str) -> &[String] {
+        self.tags.get(path).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn outgoing(&self, path: &str) -> &[Link] {
+        self.links.get(path).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    // pathを指しているリンクと、そのリンクがあるmemo（自分自身からのものは除く）
+    pub fn backlinks(&self, path: &str) -> Vec<(&str, &Link)> {
+        self.links
+            .iter()
+            .filter(|(from, _)| from.as_str() != path)
+            .flat_map(|(from, links)| links.iter().map(move |link| (from.as_str(), link)))
+            .filter(|(_, link)| link.resolved.as_deref() == Some(path))
+            .collect()
+    }
+
+    // fromをto（titleはnew_title）に移す時に書き換えが必要なmemo。
+    // from自身は移動先から見た相対リンクに直す
+    pub fn rewrites(
+        &self,
+        memo_dir: &Path,
+        from: &str,
+        to: &str,
+        new_title: &str,
+    ) -> MmemoResult<Vec<Rewrite>> {
+        let mut rewrites = Vec::new();
+        for (file, links) in &self.links {
+            // 移動後にこのmemoがある場所
+            let base = if file == from { to } else { file.as_str() };
+            let moved_dir = file == from && parent(from) != parent(to);
+
+            let mut edits: Vec<(&Link, String)> = Vec::new();
+            for link in links {
+                let Some(resolved) = link.resolved.as_deref() else {
+                    continue;
+                };
+                let new = match link.kind {
+                    LinkKind::Wiki if resolved == from => {
+                        self.wiki_target(&link.target, from, to, new_title)
+                    }
+                    LinkKind::Markdown if resolved == from => encode(&relative(base, to)),
+                    LinkKind::Markdown if moved_dir => encode(&relative(base, resolved)),
+                    _ => continue,
+                };
+                edits.push((link, new));
+            }
+            // 画像なども移動先から同じファイルを指すようにする
+            if moved_dir {
+                for link in self.attachments.get(from).into_iter().flatten() {
+                    let path = Path::new(parent(from)).join(&link.target);
+                    if let Some(path) = normalize(&path.to_string_lossy()) {
+                        edits.push((link, encode(&relative(to, &path))));
+                    }
+                }
+            }
+            if edits.is_empty() {
+                continue;
+            }
+            // 同じ行の[[...]]と(...)も位置順にしておく
+            edits.sort_by_key(|(link, _)| link.span.start);
+
+            let mut content = fs::read_to_string(memo_dir.join(file))?;
+            let mut changes = Vec::new();
+            // 後ろから置き換えれば前のリンクの位置はずれない
+            for (link, new) in edits.iter().rev() {
+                let Some(old) = content.get(link.span.clone()).map(str::to_string) else {
+                    continue;
+                };
+                if old == *new {
+                    continue;
+                }
+                content.replace_range(link.span.clone(), new);
+                let (old, new) = match link.kind {
+                    LinkKind::Wiki => (format!("[[{}]]", old), format!("[[{}]]", new)),
+                    LinkKind::Markdown => (format!("({})", old), format!("({})", new)),
+                };
+                changes.push((link.line, old, new));
+            }
+            changes.reverse();
+            if !changes.is_empty() {
+                rewrites.push(Rewrite {
+                    file: file.clone(),
+                    content,
+                    changes,
+                });
+            }
+        }
+        Ok(rewrites)
+    }
+
+    // 元と同じ書き方（パス、title、ファイル名）にする。移動後に他のmemoを指してしまう時はパスにする
+    fn wiki_target(&self, target: &str, from: &str, to: &str, new_title: &str) -> String {
+        let by_path = without_extension(to).to_string();
+        if target == from {
+            return to.to_string();
+        }
+        if without_extension(from) == target {
+            return by_path;
+        }
+
+        let taken = |name: &str| {
+            self.titles.iter().any(|(path, title)| {
+                path != from
+                    && (path == name
+                        || without_extension(path) == name
+                        || title.eq_ignore_ascii_case(name)
+                        || stem(path).eq_ignore_ascii_case(name))
+            })
+        };
+        let is_title = self
+            .titles
+            .get(from)
+            .is_some_and(|title| title.eq_ignore_ascii_case(target));
+        let name = if is_title { new_title } else { stem(to) };
+        if name.is_empty() || name.contains(['[', ']', '|', '#', '\n']) || taken(name) {
+            return by_path;
+        }
+        name.to_string()
+    }
+
+    fn resolve(&self, from: &str, kind: LinkKind, target: &str) -> Option<String> {
+        match kind {
+            LinkKind::Wiki => self.resolve_wiki(target),
+            LinkKind::Markdown => {
+                let dir = Path::new(from).parent().unwrap_or(Path::new(""));
+                let path = normalize(&dir.join(target).to_string_lossy())?;
+                self.titles.contains_key(&path).then_some(path)
+            }
+        }
+    }
+
+    // パス（拡張子は省略可）、title、ファイル名の順に探す。同じ順位で複数あればpath順で最初
+    fn resolve_wiki(&self, target: &str) -> Option<String> {
+        let target = target.trim();
+        let files = || self.titles.keys();
+        let found = files()
+            .find(|path| path.as_str() == target || without_extension(path) == target)
+            .or_else(|| {
+                self.titles
+                    .iter()
+                    .find(|(_, title)| title.eq_ignore_ascii_case(target))
+                    .map(|(path, _)| path)
+            })
+            .or_else(|| files().find(|path| stem(path).eq_ignore_ascii_case(target)));
+        found.cloned()
+    }
+}
+
+// (リンク, 画像)。コードブロックとインラインコードの中は見ない。resolvedはNoneのまま
+fn extract(content: &str) -> (Vec<Link>, Vec<Link>) {
+    let mut links = Vec::new();
+    let mut images = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\r', '\n']);
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let line = mask_code(line);
+        for caps in WIKI_LINK.captures_iter(&line) {
+            let Some(inner) = caps.get(1) else {
+                continue;
+            };
+            let part = inner.as_str().split(['|', '#']).next().unwrap_or_default();
+            let target = part.trim();
+            if target.is_empty() {
+                continue;
+            }
+            let begin = start + inner.start() + (part.len() - part.trim_start().len());
+            links.push(Link {
+                kind: LinkKind::Wiki,
+                target: target.to_string(),
+                line: i + 1,
+                span: begin..begin + target.len(),
+                resolved: None,
+            });
+        }
+        for caps in MARKDOWN_LINK.captures_iter(&line) {
+            let (Some(whole), Some(href)) = (caps.get(0), caps.get(1)) else {
+                continue;
+            };
+            if let Some((target, len)) = relative_target(href.as_str()) {
+                let begin = start + href.start();
+                let link = Link {
+                    kind: LinkKind::Markdown,
+                    target,
+                    line: i + 1,
+                    span: begin..begin + len,
+                    resolved: None,
+                };
+                match line[..whole.start()].ends_with('!') {
+                    true => images.push(link),
+                    false => links.push(link),
+                }
+            }
+        }
+    }
+    (links, images)
+}
+
+// コードの外にある`[[...]]`をreplace（引数は括弧の中身）の結果に置き換える
+pub fn replace_wiki_links(content: &str, replace: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut in_fence = false;
+
+    for line in content.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if in_fence || line.trim_start().starts_with("```") {
+            result.push_str(line);
+            continue;
+        }
+
+        let masked = mask_code(line);
+        let mut last = 0;
+        for caps in WIKI_LINK.captures_iter(&masked) {
+            let (Some(whole), Some(inner)) = (caps.get(0), caps.get(1)) else {
+                continue;
+            };
+            result.push_str(&line[last..whole.start()]);
+            result.push_str(&replace(&line[inner.range()]));
+            last = whole.end();
+        }
+        result.push_str(&line[last..]);
+    }
+    result
+}
+
+// インラインコードの中身を同じ長さの空白にする（位置を変えないため）
+fn mask_code(line: &str) -> String {
+    let mut masked = String::with_capacity(line.len());
+    let mut in_code = false;
+    for c in line.chars() {
+        if c == '`' {
+            in_code = !in_code;
+            masked.push(c);
+        } else if in_code {
+            masked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        } else {
+            masked.push(c);
+        }
+    }
+    masked
+}
+
+// (デコードしたパス, `#`や`?`より前の長さ)。URLや絶対パス、ページ内リンクはNone
+fn relative_target(href: &str) -> Option<(String, usize)> {
+    if href.contains("://") || href.starts_with(['/', '#']) || href.starts_with("mailto:") {
+        return None;
+    }
+    let path = href.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    Some((percent_decode(path), path.len()))
+}
+
+// `%20`などを戻す。壊れていればそのまま
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| s.to_string())
+}
+
+// `.`と`..`を畳む。memo_dirの外に出ればNone
+fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            _ => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+// fileのあるディレクトリからtoへの相対パス
+fn relative(file: &str, to: &str) -> String {
+    let dir: Vec<&str> = parent(file).split('/').filter(|p| !p.is_empty()).collect();
+    let target: Vec<&str> = to.split('/').collect();
+    let common = dir
+        .iter()
+        .zip(&target[..target.len() - 1])
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts = vec![".."; dir.len() - common];
+    parts.extend(&target[common..]);
+    parts.join("/")
+}
+
+// Markdownのリンクとして壊れる文字だけ`%XX`にする
+fn encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            ' ' | '(' | ')' | '%' | '#' | '?' | '<' | '>' => {
+                encoded.push_str(&format!("%{:02X}", c as u32))
+            }
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+fn without_extension(path: &str) -> &str {
+    match path.rsplit_once('.') {
+        Some((rest, ext)) if !ext.contains('/') && !rest.is_empty() && !rest.ends_with('/') => rest,
+        _ => path,
+    }
+}
+
+fn stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    without_extension(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process};
+
+    use super::*;
+
+    // テストごとのmemo_dir。filesは(path, 内容)
+    fn memo_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mmemo-link-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn rewritten(rewrites: &[Rewrite], file: &str) -> String {
+        rewrites
+            .iter()
+            .find(|r| r.file == file)
+            .map(|r| r.content.clone())
+            .unwrap_or_else(|| panic!("{} is not rewritten", file))
+    }
+
+    #[test]
+    fn extract_spans() {
+        let content = "# t\nSee [[ a b |alias]] and [[c#h]].\n[x](d%20e.md#top \"t\") ![i](f.png)\n`[[code]]` [y](https://x) [z](/abs.md)\n```\n[[fenced]]\n```\n";
+        let (links, images) = extract(content);
+
+        let targets: Vec<(&str, usize)> =
+            links.iter().map(|l| (l.target.as_str(), l.line)).collect();
+        assert_eq!(targets, [("a b", 2), ("c", 2), ("d e.md", 3)]);
+        for link in &links {
+            let written = &content[link.span.clone()];
+            match link.kind {
+                LinkKind::Wiki => assert_eq!(written, link.target),
+                LinkKind::Markdown => assert_eq!(written, "d%20e.md"),
+            }
+        }
+        assert_eq!(images.len(), 1);
+        assert_eq!(&content[images[0].span.clone()], "f.png");
+    }
+
+    #[test]
+    fn resolve_wiki_and_markdown() {
+        let dir = memo_dir(
+            "resolve",
+            &[
+                (
+                    "a.md",
+                    "---\ntitle: Alpha\n---\n[[Alpha]] [[sub/b]] [[c]] [[none]]\n",
+                ),
+                ("sub/b.md", "[up](../a.md) [same](c.md) [out](../../x.md)\n"),
+                ("sub/c.md", "---\ntitle: Gamma\n---\n"),
+            ],
+        );
+        let graph = LinkGraph::load(&dir).unwrap();
+
+        let resolved = |file: &str| -> Vec<Option<String>> {
+            graph
+                .outgoing(file)
+                .iter()
+                .map(|l| l.resolved.clone())
+                .collect()
+        };
+        assert_eq!(
+            resolved("a.md"),
+            [
+                Some("a.md".into()),
+                Some("sub/b.md".into()),
+                Some("sub/c.md".into()),
+                None
+            ]
+        );
+        assert_eq!(
+            resolved("sub/b.md"),
+            [Some("a.md".into()), Some("sub/c.md".into()), None]
+        );
+
+        // 自分自身からのリンクは除く
+        let backlinks: Vec<&str> = graph.backlinks("a.md").iter().map(|(f, _)| *f).collect();
+        assert_eq!(backlinks, ["sub/b.md"]);
+        let backlinks: Vec<&str> = graph
+            .backlinks("sub/c.md")
+            .iter()
+            .map(|(f, _)| *f)
+            .collect();
+        assert_eq!(backlinks, ["a.md", "sub/b.md"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn backlinks() {
+        let dir = memo_dir(
+            "backlinks",
+            &[
+                (
+                    "target.md",
+                    "---\ntitle: Target Memo\n---\n[[Target Memo]] [[other]]\n",
+                ),
+                (
+                    "other.md",
+                    "[[target memo#h]]\n[t](target.md#top) [[target|alias]]\n",
+                ),
+                ("sub/deep.md", "[t](../target.md) [x](target.md)\n"),
+                ("lonely.md", "[[nothing]]\n"),
+            ],
+        );
+        let graph = LinkGraph::load(&dir).unwrap();
+
+        // 同じmemoからの複数のリンクはそれぞれ出す
+        let backlinks: Vec<(&str, usize, String)> = graph
+            .backlinks("target.md")
+            .iter()
+            .map(|(from, link)| (*from, link.line, link.label()))
+            .collect();
+        assert_eq!(
+            backlinks,
+            [
+                ("other.md", 1, "[[target memo]]".to_string()),
+                ("other.md", 2, "[[target]]".to_string()),
+                ("other.md", 2, "(target.md)".to_string()),
+                ("sub/deep.md", 1, "(../target.md)".to_string()),
+            ]
+        );
+        assert_eq!(graph.backlinks("other.md").len(), 1);
+        assert!(graph.backlinks("lonely.md").is_empty());
+        assert!(graph.backlinks("nothing.md").is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(relative("a.md", "b.md"), "b.md");
+        assert_eq!(relative("a/x.md", "b.md"), "../b.md");
+        assert_eq!(relative("a/x.md", "a/b/c.md"), "b/c.md");
+        assert_eq!(relative("a/b/x.md", "a/c/y.md"), "../c/y.md");
+        assert_eq!(normalize("a/../b/./c.md").as_deref(), Some("b/c.md"));
+        assert_eq!(normalize("../x.md"), None);
+
+        for path in ["my file (1).md", "100%#?<>.md", "日本語.md", "%zz"] {
+            assert_eq!(percent_decode(&encode(path)), path);
+        }
+        assert_eq!(encode("a b.md"), "a%20b.md");
+        assert_eq!(percent_decode("bad%2"), "bad%2");
+    }
+
+    #[test]
+    fn rewrites_same_directory() {
+        let dir = memo_dir(
+            "same",
+            &[
+                ("old.md", "---\ntitle: Old\n---\n[self](old.md)\n"),
+                ("ref.md", "[[Old]] [[old]] [[old.md|x]] [l](old.md#h)\n"),
+            ],
+        );
+        let graph = LinkGraph::load(&dir).unwrap();
+        let rewrites = graph.rewrites(&dir, "old.md", "new.md", "New").unwrap();
+
+        assert_eq!(
+            rewritten(&rewrites, "old.md"),
+            "---\ntitle: Old\n---\n[self](new.md)\n"
+        );
+        assert_eq!(
+            rewritten(&rewrites, "ref.md"),
+            "[[New]] [[new]] [[new.md|x]] [l](new.md#h)\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rewrites_across_directories() {
+        let dir = memo_dir(
+            "cross",
+            &[
+                (
+                    "pic.md",
+                    "![i](img/a.png) [doc](img/a.png) [m](other.md) ![w](https://x/y.png) [[other]]\n",
+                ),
+                ("img/a.png", ""),
+                ("other.md", "[p](pic.md) [[pic]]\n"),
+                ("sub/in.md", "[p](../pic.md)\n"),
+            ],
+        );
+        let graph = LinkGraph::load(&dir).unwrap();
+        let rewrites = graph
+            .rewrites(&dir, "pic.md", "sub/pic2.md", "pic2")
+            .unwrap();
+
+        assert_eq!(
+            rewritten(&rewrites, "pic.md"),
+            "![i](../img/a.png) [doc](../img/a.png) [m](../other.md) ![w](https://x/y.png) [[other]]\n"
+        );
+        assert_eq!(
+            rewritten(&rewrites, "other.md"),
+            "[p](sub/pic2.md) [[sub/pic2]]\n"
+        );
+        assert_eq!(rewritten(&rewrites, "sub/in.md"), "[p](pic2.md)\n");
+        // 画像はリンクとして数えない（[doc](...)の1つだけ）
+        assert_eq!(graph.backlinks("img/a.png").len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rewrites_ambiguous_name_uses_path() {
+        let dir = memo_dir(
+            "ambiguous",
+            &[("a.md", "x\n"), ("b.md", "x\n"), ("ref.md", "[[a]]\n")],
+        );
+        let graph = LinkGraph::load(&dir).unwrap();
+        let rewrites = graph.rewrites(&dir, "a.md", "sub/b.md", "b").unwrap();
+        assert_eq!(rewritten(&rewrites, "ref.md"), "[[sub/b]]\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
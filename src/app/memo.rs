@@ -0,0 +1,562 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use chrono::NaiveDate;
+
+use crate::app::error::{MmemoError, MmemoResult};
+
+const DELIMITER: &str = "---";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub tags: Vec<String>,
+    pub extra: BTreeMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct Memo {
+    // memo_dirからの相対パス
+    pub path: String,
+    // front matterが無いファイルはNone
+    pub front_matter: Option<FrontMatter>,
+    pub body: String,
+}
+
+impl Memo {
+    pub fn load(memo_dir: &Path, path: &str) -> MmemoResult<Self> {
+        let content = fs::read_to_string(memo_dir.join(path))?;
+        Memo::parse(path, &content)
+    }
+
+    pub fn parse(path: &str, content: &str) -> MmemoResult<Self> {
+        let (front_matter, body) = match split_front_matter(content) {
+            Some((raw, body)) => {
+                let front_matter =
+                    FrontMatter::parse(raw).map_err(|message| MmemoError::Parse {
+                        message: format!("{}: {}", path, message),
+                    })?;
+                (Some(front_matter), body)
+            }
+            None => {
+                if is_delimiter(content.lines().next().unwrap_or_default()) {
+                    return Err(MmemoError::Parse {
+                        message: format!("{}: front matter is not closed", path),
+                    });
+                }
+                (None, content)
+            }
+        };
+
+        Ok(Memo {
+            path: path.to_string(),
+            front_matter,
+            body: body.to_string(),
+        })
+    }
+
+    // front matterのtitle、無ければ拡張子を除いたファイル名
+    pub fn title(&self) -> String {
+        self.front_matter
+            .as_ref()
+            .and_then(|fm| fm.title.clone())
+            .unwrap_or_else(|| {
+                Path::new(&self.path)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| self.path.clone())
+            })
+    }
+
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.front_matter.as_ref().and_then(|fm| fm.date)
+    }
+
+    // front matterのtagsと本文中の`#tag`を合わせたもの（重複なし）
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        let front = self
+            .front_matter
+            .iter()
+            .flat_map(|fm| fm.tags.iter().cloned());
+
+        for tag in front.chain(inline_tags(&self.body)) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        let own = self.tags();
+        tags.iter()
+            .all(|tag| own.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+}
+
+// front matterのtitleを書き換えた内容。front matterが無ければそのまま返す
+pub fn set_title(content: &str, title: &str) -> String {
+    let Some((raw, _)) = split_front_matter(content) else {
+        return content.to_string();
+    };
+
+    let start = raw.as_ptr() as usize - content.as_ptr() as usize;
+    let value = quote_value(title);
+
+    let mut replaced = false;
+    let mut lines: Vec<String> = Vec::new();
+    for line in raw.lines() {
+        let is_title = line
+            .split_once(':')
+            .is_some_and(|(key, _)| key.trim() == "title" && !line.starts_with([' ', '\t']));
+        if is_title && !replaced {
+            lines.push(format!("title: {}", value));
+            replaced = true;
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    if !replaced {
+        lines.insert(0, format!("title: {}", value));
+    }
+
+    replace_front_matter(content, start, raw.len(), lines)
+}
+
+// front matterに書く値。そのままだとlistやコメントとして読まれるものは""で囲む
+pub fn quote_value(value: &str) -> String {
+    let needs_quote = value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.starts_with([
+            '"', '\'', '[', '{', '#', '-', '&', '*', '!', '|', '>', '%', '@', '`',
+        ]);
+    if needs_quote {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// front matterのtagsを`tags: [a, b]`に書き換える。front matterが無ければ作り、tagsが空なら消す
+// 本文の`#tag`はそのまま
+pub fn set_tags(content: &str, tags: &[String]) -> String {
+    // `,`を含むtagは[]の区切りと紛れるので常に括る
+    let items: Vec<String> = tags
+        .iter()
+        .map(|tag| {
+            if tag.contains(',') {
+                format!("\"{}\"", tag.replace('"', "\\\""))
+            } else {
+                quote_value(tag)
+            }
+        })
+        .collect();
+    let value = format!("tags: [{}]", items.join(", "));
+    let Some((raw, _)) = split_front_matter(content) else {
+        if tags.is_empty() {
+            return content.to_string();
+        }
+        return format!("{}\n{}\n{}\n{}", DELIMITER, value, DELIMITER, content);
+    };
+
+    let start = raw.as_ptr() as usize - content.as_ptr() as usize;
+    let mut replaced = false;
+    let mut in_tags = false;
+    let mut lines: Vec<String> = Vec::new();
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        let is_tags = line
+            .split_once(':')
+            .is_some_and(|(key, _)| key.trim() == "tags" && !line.starts_with([' ', '\t']));
+        if is_tags {
+            if !replaced && !tags.is_empty() {
+                lines.push(value.clone());
+            }
+            replaced = true;
+            in_tags = true;
+            continue;
+        }
+        // `tags:`の下の`- item`
+        if in_tags && (trimmed.starts_with("- ") || trimmed == "-") {
+            continue;
+        }
+        if !trimmed.is_empty() {
+            in_tags = false;
+        }
+        lines.push(line.to_string());
+    }
+    if !replaced && !tags.is_empty() {
+        lines.push(value);
+    }
+
+    replace_front_matter(content, start, raw.len(), lines)
+}
+
+// contentのstartからlen byteのfront matterをlinesで置き換える
+fn replace_front_matter(content: &str, start: usize, len: usize, lines: Vec<String>) -> String {
+    let mut result = content[..start].to_string();
+    for line in lines {
+        result.push_str(&line);
+        result.push('\n');
+    }
+    result.push_str(&content[start + len..]);
+    result
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+// 本文中の`#tag`を拾う。見出し、コードブロック、インラインコード、`#123`は除く
+fn inline_tags(body: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut in_fence = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut prev: Option<char> = None;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && prev.is_none_or(char::is_whitespace) {
+                let tag: String = line[i + 1..]
+                    .chars()
+                    .take_while(|c| is_tag_char(*c))
+                    .collect();
+                let tag = tag.trim_end_matches(['-', '/']);
+                if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+                    tags.push(tag.to_string());
+                }
+                while chars.next_if(|(_, c)| is_tag_char(*c)).is_some() {}
+            }
+            prev = Some(c);
+        }
+    }
+    tags
+}
+
+fn is_delimiter(line: &str) -> bool {
+    line.trim_end() == DELIMITER
+}
+
+// `---`で囲まれたブロックと本文に分ける。front matterが無ければNone
+fn split_front_matter(content: &str) -> Option<(&str, &str)> {
+    let first = content.lines().next()?;
+    if !is_delimiter(first) {
+        return None;
+    }
+
+    let rest = &content[first.len()..];
+    let rest = rest.strip_prefix("\r\n").or(rest.strip_prefix('\n'))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if is_delimiter(line.trim_end_matches(['\r', '\n'])) {
+            let raw = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return Some((raw, body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+impl FrontMatter {
+    // 無いtitle、date、tagsは出さない
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        if let Some(title) = &self.title {
+            map.insert("title".into(), title.clone().into());
+        }
+        if let Some(date) = self.date {
+            map.insert("date".into(), date.format("%Y-%m-%d").to_string().into());
+        }
+        if !self.tags.is_empty() {
+            map.insert("tags".into(), self.tags.clone().into());
+        }
+        for (key, value) in &self.extra {
+            let value = match value {
+                Value::String(s) => s.clone().into(),
+                Value::List(items) => items.clone().into(),
+            };
+            map.insert(key.clone(), value);
+        }
+        serde_json::Value::Object(map)
+    }
+
+    // YAMLのサブセット（`key: value`、`[a, b]`、`- item`）だけを扱う
+    fn parse(raw: &str) -> Result<Self, String> {
+        let mut entries: Vec<(String, Value)> = Vec::new();
+
+        for (i, line) in raw.lines().enumerate() {
+            let row = i + 2;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some(item) = trimmed
+                .strip_prefix("- ")
+                .or((trimmed == "-").then_some(""))
+            {
+                let Some((key, value)) = entries.last_mut() else {
+                    return Err(format!("line {}: list item without a key", row));
+                };
+                match value {
+                    Value::List(items) => items.push(unquote(item.trim())),
+                    Value::String(s) if s.is_empty() => {
+                        *value = Value::List(vec![unquote(item.trim())])
+                    }
+                    Value::String(_) => {
+                        return Err(format!("line {}: {} already has a value", row, key));
+                    }
+                }
+                continue;
+            }
+
+            if line.starts_with([' ', '\t']) {
+                return Err(format!("line {}: nested values are not supported", row));
+            }
+
+            let Some((key, value)) = trimmed.split_once(':') else {
+                return Err(format!("line {}: expected `key: value`", row));
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(format!("line {}: empty key", row));
+            }
+            if entries.iter().any(|(k, _)| k == key) {
+                return Err(format!("line {}: duplicate key {}", row, key));
+            }
+
+            entries.push((key.to_string(), parse_value(value.trim())?));
+        }
+
+        let mut front_matter = FrontMatter::default();
+        for (key, value) in entries {
+            match key.as_str() {
+                "title" => front_matter.title = as_string(value).filter(|s| !s.is_empty()),
+                "date" => {
+                    front_matter.date = match as_string(value).filter(|s| !s.is_empty()) {
+                        Some(s) => Some(parse_date(&s)?),
+                        None => None,
+                    }
+                }
+                "tags" => front_matter.tags = as_tags(value),
+                _ => {
+                    front_matter.extra.insert(key, value);
+                }
+            }
+        }
+
+        Ok(front_matter)
+    }
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    if let Some(inner) = value.strip_prefix('[') {
+        let Some(inner) = inner.strip_suffix(']') else {
+            return Err(format!("unclosed list: {}", value));
+        };
+        let items = split_items(inner)
+            .into_iter()
+            .map(|s| unquote(s.trim()))
+            .filter(|s| !s.is_empty())
+            .collect();
+        return Ok(Value::List(items));
+    }
+
+    Ok(Value::String(unquote(value)))
+}
+
+// `[a, "b, c"]`の中身を`,`で分ける。括られた中の`,`では分けない
+fn split_items(inner: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            // 項目の先頭の時だけ括りとみなす
+            None if (c == '"' || c == '\'') && inner[start..i].trim().is_empty() => {
+                quote = Some(c);
+            }
+            None if c == ',' => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+            }
+            None => {}
+        }
+    }
+    items.push(&inner[start..]);
+    items
+}
+
+fn unquote(s: &str) -> String {
+    if let Some(inner) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return inner.replace("\\\"", "\"");
+    }
+    if let Some(inner) = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        return inner.to_string();
+    }
+    s.to_string()
+}
+
+fn as_string(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::List(items) => (!items.is_empty()).then(|| items.join(", ")),
+    }
+}
+
+fn as_tags(value: Value) -> Vec<String> {
+    let items = match value {
+        Value::String(s) => s.split([',', ' ']).map(str::to_string).collect::<Vec<_>>(),
+        Value::List(items) => items,
+    };
+
+    items
+        .iter()
+        .map(|t| t.trim().trim_start_matches('#').to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+// `YYYY-MM-DD`の後ろに時刻が付いていても日付部分だけ読む
+fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    let date = s.get(..10).unwrap_or(s);
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("invalid date: {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Result<FrontMatter, String> {
+        FrontMatter::parse(raw)
+    }
+
+    #[test]
+    fn fields() {
+        let fm = parse("title: Hello\ndate: 2026-10-18 09:30\ntags: [a, \"b\", #c]\nauthor: me\n")
+            .unwrap();
+        assert_eq!(fm.title.as_deref(), Some("Hello"));
+        assert_eq!(fm.date, NaiveDate::from_ymd_opt(2026, 10, 18));
+        assert_eq!(fm.tags, ["a", "b", "c"]);
+        assert_eq!(fm.extra["author"], Value::String("me".into()));
+    }
+
+    #[test]
+    fn block_lists_and_comments() {
+        let fm = parse("# comment\ntags:\n  - a\n  - 'b c'\n\nrefs:\n- x\n").unwrap();
+        assert_eq!(fm.tags, ["a", "b c"]);
+        assert_eq!(fm.extra["refs"], Value::List(vec!["x".into()]));
+    }
+
+    #[test]
+    fn string_tags_and_empty_values() {
+        let fm = parse("title:\ndate: \ntags: a, b #c\n").unwrap();
+        assert_eq!(fm.title, None);
+        assert_eq!(fm.date, None);
+        assert_eq!(fm.tags, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn quoted_values() {
+        let fm = parse("title: \"a: b\"\n").unwrap();
+        assert_eq!(fm.title.as_deref(), Some("a: b"));
+        let fm = parse("title: '[WIP] x'\n").unwrap();
+        assert_eq!(fm.title.as_deref(), Some("[WIP] x"));
+    }
+
+    #[test]
+    fn errors() {
+        for raw in [
+            "title: [WIP] x\n",
+            "- a\n",
+            "tags: a\n- b\n",
+            "a:\n  b: c\n",
+            "no colon\n",
+            ": x\n",
+            "a: 1\na: 2\n",
+            "date: 18/10/2026\n",
+        ] {
+            assert!(parse(raw).is_err(), "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn split() {
+        let memo = Memo::parse("a.md", "---\ntitle: T\n---\nbody\n").unwrap();
+        assert_eq!(memo.title(), "T");
+        assert_eq!(memo.body, "body\n");
+
+        let memo = Memo::parse("dir/b.md", "no front matter\n").unwrap();
+        assert!(memo.front_matter.is_none());
+        assert_eq!(memo.title(), "b");
+
+        assert!(Memo::parse("c.md", "---\ntitle: T\n").is_err());
+    }
+
+    #[test]
+    fn set_title_round_trip() {
+        for title in [
+            "plain",
+            "[WIP] fix parser",
+            "a: b",
+            "x #y",
+            "\"q\"",
+            "- item",
+            "end:",
+        ] {
+            let content = set_title("---\ntitle: old\n---\n", title);
+            let memo = Memo::parse("a.md", &content).unwrap();
+            assert_eq!(memo.title(), title, "{}", content);
+        }
+    }
+
+    #[test]
+    fn set_tags_round_trip() {
+        let tags: Vec<String> = [
+            "plain",
+            "a: b",
+            "x #y",
+            "\"q\"",
+            "'s'",
+            "-dash",
+            "[wip]",
+            "a,b",
+            "it's",
+            "日本語",
+        ]
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+        for content in ["---\ntitle: t\ntags: [old]\n---\nbody\n", "body\n"] {
+            let content = set_tags(content, &tags);
+            let memo = Memo::parse("a.md", &content).unwrap();
+            assert_eq!(memo.front_matter.unwrap().tags, tags, "{}", content);
+        }
+    }
+}
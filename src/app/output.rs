@@ -0,0 +1,146 @@
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    str::FromStr,
+};
+
+use crate::app::error::MmemoResult;
+
+// list/grepの--format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    // 全体で1つの配列
+    Json,
+    // 1行に1つのobject
+    Jsonl,
+    // header無し、タブ区切り
+    Tsv,
+}
+
+pub struct ParseOutputFormatError;
+
+impl FromStr for OutputFormat {
+    type Err = ParseOutputFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" | "ndjson" => Ok(OutputFormat::Jsonl),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(ParseOutputFormatError),
+        }
+    }
+}
+
+// graphの--format
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GraphFormat {
+    // Graphviz
+    #[default]
+    Dot,
+    Json,
+    Mermaid,
+}
+
+pub struct ParseGraphFormatError;
+
+impl FromStr for GraphFormat {
+    type Err = ParseGraphFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" | "graphviz" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(ParseGraphFormatError),
+        }
+    }
+}
+
+// stdoutが端末で、NO_COLORが空でない値で設定されていない時だけ色を付ける
+pub fn color_enabled() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+// json/jsonlで出す。Textは呼ぶ側で扱う
+pub fn write_json(format: OutputFormat, records: &[serde_json::Value]) -> MmemoResult<()> {
+    let mut out = io::stdout().lock();
+    match format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string(records).map_err(io::Error::other)?;
+            writeln!(out, "{}", json)?;
+        }
+        _ => {
+            for record in records {
+                let json = serde_json::to_string(record).map_err(io::Error::other)?;
+                writeln!(out, "{}", json)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// タブ、改行、`\`をエスケープした1行
+pub fn write_tsv(rows: &[Vec<String>]) -> MmemoResult<()> {
+    let mut out = io::stdout().lock();
+    for row in rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|field| {
+                field
+                    .replace('\\', "\\\\")
+                    .replace('\t', "\\t")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r")
+            })
+            .collect();
+        writeln!(out, "{}", fields.join("\t"))?;
+    }
+    Ok(())
+}
+
+// listのtextで出す列
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListColumn {
+    Path,
+    Title,
+    // front matterのdate、無ければファイルの作成日時（取れなければ更新日時）
+    Created,
+    Modified,
+    Tags,
+    Words,
+    Size,
+}
+
+pub struct ParseListColumnError;
+
+impl FromStr for ListColumn {
+    type Err = ParseListColumnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "path" | "name" => Ok(ListColumn::Path),
+            "title" => Ok(ListColumn::Title),
+            "created" | "date" => Ok(ListColumn::Created),
+            "modified" => Ok(ListColumn::Modified),
+            "tags" => Ok(ListColumn::Tags),
+            "words" => Ok(ListColumn::Words),
+            "size" => Ok(ListColumn::Size),
+            _ => Err(ParseListColumnError),
+        }
+    }
+}
+
+pub const DEFAULT_LIST_COLUMNS: &[ListColumn] =
+    &[ListColumn::Path, ListColumn::Title, ListColumn::Created];
+
+// `path,title,tags`のようなカンマ区切り
+pub fn parse_columns(s: &str) -> Option<Vec<ListColumn>> {
+    let columns = s
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<Vec<ListColumn>>>()?;
+    (!columns.is_empty()).then_some(columns)
+}
@@ -24,3 +24,7 @@ pub fn config_path() -> MmemoResult<PathBuf> {
 pub fn template_path() -> MmemoResult<PathBuf> {
     Ok(config_dir()?.join("template.txt"))
 }
+
+pub fn templates_dir() -> MmemoResult<PathBuf> {
+    Ok(config_dir()?.join("templates"))
+}
@@ -0,0 +1,46 @@
+use termimad::{Alignment, FmtText, MadSkin};
+
+use crate::app::{link, memo::Memo};
+
+pub fn skin() -> MadSkin {
+    let mut skin = MadSkin::default();
+    for header in &mut skin.headers {
+        header.align = Alignment::Left;
+    }
+    skin
+}
+
+// front matterはmarkdownとして描画しない。壊れていたらそのまま返す
+pub fn memo_text(path: &str, content: String) -> String {
+    match Memo::parse(path, &content) {
+        Ok(memo) => memo.body,
+        Err(_) => content,
+    }
+}
+
+// widthで折り返した描画済みの行（ANSIエスケープ付き）
+pub fn markdown_lines(text: &str, width: usize) -> Vec<String> {
+    let skin = skin();
+    FmtText::from(&skin, &mark_links(text), Some(width))
+        .to_string()
+        .lines()
+        .map(highlight_links)
+        .collect()
+}
+
+// markdownとして描画されても残る印（私用領域の文字）。描画後に色付きの`[[`、`]]`にする
+const LINK_OPEN: &str = "\u{E000}\u{E000}";
+const LINK_CLOSE: &str = "\u{E001}\u{E001}";
+
+// `[[target|別名]]`は`[[別名]]`と見せる
+fn mark_links(text: &str) -> String {
+    link::replace_wiki_links(text, |inner| {
+        let shown = inner.split_once('|').map_or(inner, |(_, alias)| alias);
+        format!("{}{}{}", LINK_OPEN, shown.trim(), LINK_CLOSE)
+    })
+}
+
+fn highlight_links(line: &str) -> String {
+    line.replace(LINK_OPEN, "\x1b[36;4m[[")
+        .replace(LINK_CLOSE, "]]\x1b[24;39m")
+}
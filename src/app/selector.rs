@@ -1,22 +1,43 @@
+use std::path::Path;
+
 use crate::app::{
-    config::SelectorKind,
+    config::{Config, PreviewKind, SelectorKind},
     selector::{
         builtin::Builtin,
         external::{Fzf, Skim},
+        preview::Preview,
     },
 };
 
 pub mod builtin;
 pub mod core;
 pub mod external;
+pub mod preview;
 
 pub trait Selector {
-    fn select(&self, items: Vec<String>) -> std::io::Result<Option<String>>;
+    // queryは入力欄の初期値
+    fn select(&self, items: Vec<String>, query: &str) -> std::io::Result<Option<String>>;
+    // 複数選択。builtinはTab、fzf/skimは--multiで選ぶ
+    fn select_multi(&self, items: Vec<String>, query: &str) -> std::io::Result<Vec<String>>;
 }
 
-pub fn selector_select(selector: &SelectorKind) -> Box<dyn Selector> {
-    match selector {
-        SelectorKind::Builtin => Box::new(Builtin),
+// previewはmemo_dirからの相対パスとしてitemを読む
+pub fn selector_select(config: &Config, memo_dir: &Path) -> Box<dyn Selector> {
+    match config.selector {
+        SelectorKind::Builtin => {
+            let markdown = match config.preview {
+                PreviewKind::Markdown => Some(true),
+                PreviewKind::Raw => Some(false),
+                PreviewKind::None => None,
+            };
+            let preview = markdown.map(|markdown| Preview {
+                dir: memo_dir.to_path_buf(),
+                markdown,
+                position: config.preview_position,
+                width: config.preview_width,
+            });
+            Box::new(Builtin { preview })
+        }
         SelectorKind::Fzf => Box::new(Fzf),
         SelectorKind::Skim => Box::new(Skim),
     }
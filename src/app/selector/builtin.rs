@@ -15,28 +15,59 @@ use crossterm::{
 };
 
 use crate::app::selector::Selector;
-use crate::app::selector::core::{MatchResult, Matcher};
+use crate::app::selector::core::{MatchResult, Matcher, Source};
+use crate::app::selector::preview::{Preview, Rect, layout};
 
-pub struct Builtin;
+pub struct Builtin {
+    pub preview: Option<Preview>,
+}
 
 impl Selector for Builtin {
-    fn select(&self, items: Vec<String>) -> io::Result<Option<String>> {
-        let mut matcher = Matcher::new(items);
+    fn select(&self, items: Vec<String>, query: &str) -> io::Result<Option<String>> {
+        Ok(self.run(Box::new(Matcher::new(items)), query, false)?.pop())
+    }
+
+    fn select_multi(&self, items: Vec<String>, query: &str) -> io::Result<Vec<String>> {
+        self.run(Box::new(Matcher::new(items)), query, true)
+    }
+}
+
+impl Builtin {
+    // 候補を入力のたびにsourceから作り直す（live grepなど）
+    pub fn select_live(&self, source: Box<dyn Source>, query: &str) -> io::Result<Option<String>> {
+        Ok(self.run(source, query, false)?.pop())
+    }
 
+    // multiならTabで印を付けた全件、印が無ければカーソル位置の1件を返す
+    fn run(
+        &self,
+        mut source: Box<dyn Source>,
+        query: &str,
+        multi: bool,
+    ) -> io::Result<Vec<String>> {
         enable_raw_mode()?;
         execute!(stderr(), EnterAlternateScreen, Hide)?;
 
         let (cols, rows) = size()?;
+        let (list_area, preview_area) = layout(cols, rows, self.preview.as_ref());
 
-        let mut input = String::new();
+        let mut input = query.to_string();
         let mut selected_index = 0;
         let mut start_pos = 0;
 
-        let max_items = rows as usize - 4;
-        let all_items = matcher.items.len();
-        let scroll_next_pos = rows as usize - 11;
+        let max_items = list_area.height as usize - 4;
+        let item_width = list_area.width as usize - 5;
+        let all_items = source.total();
+        let scroll_next_pos = max_items.saturating_sub(7);
         let scroll_previous_pos = 5;
 
+        // (表示中のitem, 整形済みの行)
+        let mut preview_cache: Option<(String, Vec<String>)> = None;
+        let mut preview_scroll = 0;
+
+        // 印を付けた順に保持する
+        let mut marked: Vec<String> = Vec::new();
+
         let (query_tx, query_rx): (Sender<String>, Receiver<String>) = mpsc::channel();
         let (result_tx, result_rx): (Sender<Vec<MatchResult>>, Receiver<Vec<MatchResult>>) =
             mpsc::channel();
@@ -44,7 +75,7 @@ impl Selector for Builtin {
 
         thread::spawn(move || {
             while let Ok(input) = query_rx.recv() {
-                let result = matcher.fuzzy_match(&input);
+                let result = source.search(&input);
                 if result_tx.send(result).is_err() {
                     break;
                 }
@@ -73,10 +104,36 @@ impl Selector for Builtin {
             if needs_redraw {
                 let mut stderr = stderr().lock();
                 execute!(stderr, Clear(terminal::ClearType::All))?;
-                draw_outline(&mut stderr, cols, rows)?;
+                draw_outline(&mut stderr, list_area)?;
                 draw_input(&mut stderr, &input)?;
-                draw_count(&mut stderr, result.len(), all_items)?;
-                draw_items(&mut stderr, selected_index, &items[..])?;
+                draw_count(&mut stderr, result.len(), all_items, marked.len())?;
+                draw_items(&mut stderr, selected_index, &items[..], &marked, item_width)?;
+
+                if let (Some(preview), Some(area)) = (&self.preview, preview_area) {
+                    let current = items.get(selected_index);
+                    if preview_cache.as_ref().map(|(item, _)| item.as_str())
+                        != current.map(|r| r.item.as_str())
+                    {
+                        preview_scroll = 0;
+                        let width = area.width as usize - 4;
+                        preview_cache = current.map(|r| match &r.target {
+                            // 当たった行が枠の上から1/3あたりに来るようにする
+                            Some((path, line)) => {
+                                preview_scroll = line.saturating_sub(area.height as usize / 3);
+                                (r.item.clone(), preview.lines_at(path, *line, width))
+                            }
+                            None => (r.item.clone(), preview.lines(&r.item, width)),
+                        });
+                        if let Some((_, lines)) = &preview_cache {
+                            preview_scroll = preview_scroll.min(lines.len().saturating_sub(1));
+                        }
+                    }
+                    draw_outline(&mut stderr, area)?;
+                    if let Some((_, lines)) = &preview_cache {
+                        draw_preview(&mut stderr, area, &lines[preview_scroll..])?;
+                    }
+                }
+
                 stderr.flush()?;
                 needs_redraw = false;
             }
@@ -86,12 +143,12 @@ impl Selector for Builtin {
                 match read()? {
                     Event::Key(KeyEvent {
                         code: KeyCode::Esc, ..
-                    }) => break None,
+                    }) => break Vec::new(),
                     Event::Key(KeyEvent {
                         code: KeyCode::Char('c'),
                         modifiers: KeyModifiers::CONTROL,
                         ..
-                    }) => break None,
+                    }) => break Vec::new(),
                     Event::Key(KeyEvent {
                         code: KeyCode::Char('p'),
                         modifiers: KeyModifiers::CONTROL,
@@ -143,6 +200,47 @@ impl Selector for Builtin {
                             start_pos += 1;
                         }
                     }
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Char('d'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    })
+                    | Event::Key(KeyEvent {
+                        code: KeyCode::PageDown,
+                        ..
+                    }) => {
+                        if let (Some((_, lines)), Some(area)) = (&preview_cache, preview_area) {
+                            let step = (area.height as usize / 2).max(1);
+                            preview_scroll =
+                                (preview_scroll + step).min(lines.len().saturating_sub(1));
+                        }
+                    }
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Char('u'),
+                        modifiers: KeyModifiers::CONTROL,
+                        ..
+                    })
+                    | Event::Key(KeyEvent {
+                        code: KeyCode::PageUp,
+                        ..
+                    }) => {
+                        if let Some(area) = preview_area {
+                            let step = (area.height as usize / 2).max(1);
+                            preview_scroll = preview_scroll.saturating_sub(step);
+                        }
+                    }
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Tab, ..
+                    }) if multi => {
+                        if let Some(current) = items.get(selected_index) {
+                            match marked.iter().position(|m| *m == current.item) {
+                                Some(i) => {
+                                    marked.remove(i);
+                                }
+                                None => marked.push(current.item.clone()),
+                            }
+                        }
+                    }
                     Event::Key(KeyEvent {
                         code: KeyCode::Backspace,
                         ..
@@ -161,10 +259,13 @@ impl Selector for Builtin {
                         code: KeyCode::Enter,
                         ..
                     }) => {
+                        if !marked.is_empty() {
+                            break marked;
+                        }
                         if items.is_empty() {
-                            break None;
+                            break Vec::new();
                         }
-                        break Some(items[selected_index].item.clone());
+                        break vec![items[selected_index].item.clone()];
                     }
                     _ => continue,
                 }
@@ -178,7 +279,14 @@ impl Selector for Builtin {
     }
 }
 
-fn draw_outline(stderr: &mut impl Write, cols: u16, rows: u16) -> io::Result<()> {
+pub fn draw_outline(stderr: &mut impl Write, area: Rect) -> io::Result<()> {
+    let Rect {
+        x,
+        y,
+        width: cols,
+        height: rows,
+    } = area;
+
     execute!(
         stderr,
         SetForegroundColor(Color::Rgb {
@@ -188,21 +296,21 @@ fn draw_outline(stderr: &mut impl Write, cols: u16, rows: u16) -> io::Result<()>
         })
     )?;
 
-    execute!(stderr, MoveTo(0, 0), Print("╭"))?;
+    execute!(stderr, MoveTo(x, y), Print("╭"))?;
     for _ in 1..cols - 1 {
         execute!(stderr, Print("─"))?;
     }
-    execute!(stderr, MoveTo(cols - 1, 0), Print("╮"))?;
+    execute!(stderr, MoveTo(x + cols - 1, y), Print("╮"))?;
 
     for c in 1..rows - 1 {
-        execute!(stderr, MoveTo(cols - 1, c), Print("│"))?;
+        execute!(stderr, MoveTo(x + cols - 1, y + c), Print("│"))?;
     }
-    execute!(stderr, MoveTo(cols - 1, rows - 1), Print("╯"))?;
+    execute!(stderr, MoveTo(x + cols - 1, y + rows - 1), Print("╯"))?;
 
     for c in 1..rows - 1 {
-        execute!(stderr, MoveTo(0, c), Print("│"))?;
+        execute!(stderr, MoveTo(x, y + c), Print("│"))?;
     }
-    execute!(stderr, MoveTo(0, rows - 1), Print("╰"))?;
+    execute!(stderr, MoveTo(x, y + rows - 1), Print("╰"))?;
 
     for _ in 1..cols - 1 {
         execute!(stderr, Print("─"))?;
@@ -213,7 +321,34 @@ fn draw_outline(stderr: &mut impl Write, cols: u16, rows: u16) -> io::Result<()>
     Ok(())
 }
 
-fn draw_count(stderr: &mut impl Write, match_count: usize, items_count: usize) -> io::Result<()> {
+pub fn draw_preview(stderr: &mut impl Write, area: Rect, lines: &[String]) -> io::Result<()> {
+    let max_lines = area.height as usize - 2;
+    for (i, line) in lines.iter().take(max_lines).enumerate() {
+        execute!(
+            stderr,
+            MoveTo(area.x + 2, area.y + 1 + i as u16),
+            Print(line),
+            Print("\x1b[0m"),
+        )?;
+    }
+    Ok(())
+}
+
+pub fn draw_count(
+    stderr: &mut impl Write,
+    match_count: usize,
+    items_count: usize,
+    marked_count: usize,
+) -> io::Result<()> {
+    let count = if marked_count > 0 {
+        format!(
+            "{}/{} ({} selected)",
+            match_count, items_count, marked_count
+        )
+    } else {
+        format!("{}/{}", match_count, items_count)
+    };
+
     execute!(
         stderr,
         SetForegroundColor(Color::Rgb {
@@ -222,7 +357,7 @@ fn draw_count(stderr: &mut impl Write, match_count: usize, items_count: usize) -
             b: 110
         }),
         MoveTo(4, 2),
-        Print(format!("{}/{}", match_count, items_count)),
+        Print(count),
     )?;
 
     execute!(stderr, ResetColor)?;
@@ -230,7 +365,7 @@ fn draw_count(stderr: &mut impl Write, match_count: usize, items_count: usize) -
     Ok(())
 }
 
-fn draw_input(stderr: &mut impl Write, input: &str) -> io::Result<()> {
+pub fn draw_input(stderr: &mut impl Write, input: &str) -> io::Result<()> {
     execute!(
         stderr,
         MoveTo(2, 1),
@@ -246,16 +381,18 @@ fn draw_input(stderr: &mut impl Write, input: &str) -> io::Result<()> {
     Ok(())
 }
 
-fn draw_items(
+pub fn draw_items(
     stderr: &mut impl Write,
     selected_index: usize,
     results: &[&MatchResult],
+    marked: &[String],
+    max_width: usize,
 ) -> io::Result<()> {
     for (r_i, result) in results.iter().enumerate() {
         let is_selected = r_i == selected_index;
         let mut line = String::new();
 
-        for (c_i, char) in result.item.char_indices() {
+        for (c_i, char) in result.item.char_indices().take(max_width) {
             if result.hits.contains(&c_i) {
                 line.push_str("\x1b[32m");
             } else {
@@ -279,6 +416,15 @@ fn draw_items(
                 ResetColor
             )?;
         }
+        if marked.contains(&result.item) {
+            execute!(
+                stderr,
+                MoveTo(3, (r_i + 3) as u16),
+                SetForegroundColor(Color::Magenta),
+                Print("+"),
+                ResetColor
+            )?;
+        }
         execute!(stderr, MoveTo(4, (r_i + 3) as u16), Print(line),)?;
     }
     Ok(())
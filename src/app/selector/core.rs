@@ -27,6 +27,7 @@ impl Matcher {
                     kind: MatchKind::Unfiltered,
                     score: None,
                     hits: Vec::new(),
+                    target: None,
                 })
                 .collect();
         }
@@ -50,7 +51,7 @@ impl Matcher {
 
         self.cache = Some((input.to_string(), matched_indices));
 
-        result.sort_by(|a, b| (a.kind, a.score).cmp(&(b.kind, b.score)));
+        result.sort_by_key(|a| (a.kind, a.score));
         result
     }
 
@@ -61,6 +62,7 @@ impl Matcher {
                 kind: MatchKind::Exact,
                 score: None,
                 hits: (0..input.len()).collect(),
+                target: None,
             })
         } else if item.starts_with(input) {
             Some(MatchResult {
@@ -68,6 +70,7 @@ impl Matcher {
                 kind: MatchKind::Prefix,
                 score: None,
                 hits: (0..input.len()).collect(),
+                target: None,
             })
         } else if item.contains(input) {
             let input_char = input.chars().next()?;
@@ -79,6 +82,7 @@ impl Matcher {
                 kind: MatchKind::Substring,
                 score: Some(distance),
                 hits: (distance..distance + input.len()).collect(),
+                target: None,
             })
         } else {
             // Fuzzy
@@ -109,6 +113,7 @@ impl Matcher {
                 kind: MatchKind::Fuzzy,
                 score: Some(score),
                 hits,
+                target: None,
             })
         }
     }
@@ -120,6 +125,38 @@ pub struct MatchResult {
     kind: MatchKind,
     score: Option<usize>,
     pub hits: Vec<usize>,
+    // previewで開くファイルと行（1始まり）。Noneならitemをファイルとして開く
+    pub target: Option<(String, usize)>,
+}
+
+impl MatchResult {
+    // 並べ替えずにそのまま出す結果
+    pub fn new(item: String, hits: Vec<usize>, target: Option<(String, usize)>) -> Self {
+        MatchResult {
+            item,
+            kind: MatchKind::Unfiltered,
+            score: None,
+            hits,
+            target,
+        }
+    }
+}
+
+// selectorに出す候補。入力が変わるたびに別スレッドでsearchを呼ぶ
+pub trait Source: Send {
+    // 件数表示の分母
+    fn total(&self) -> usize;
+    fn search(&mut self, input: &str) -> Vec<MatchResult>;
+}
+
+impl Source for Matcher {
+    fn total(&self) -> usize {
+        self.items.len()
+    }
+
+    fn search(&mut self, input: &str) -> Vec<MatchResult> {
+        self.fuzzy_match(input)
+    }
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
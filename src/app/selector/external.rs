@@ -7,23 +7,38 @@ use crate::app::selector::Selector;
 
 pub struct Fzf;
 impl Selector for Fzf {
-    fn select(&self, items: Vec<String>) -> std::io::Result<Option<String>> {
-        run("fzf", items)
+    fn select(&self, items: Vec<String>, query: &str) -> std::io::Result<Option<String>> {
+        Ok(run("fzf", items, query, false)?.pop())
+    }
+
+    fn select_multi(&self, items: Vec<String>, query: &str) -> std::io::Result<Vec<String>> {
+        run("fzf", items, query, true)
     }
 }
 
 pub struct Skim;
 impl Selector for Skim {
-    fn select(&self, items: Vec<String>) -> std::io::Result<Option<String>> {
-        run("sk", items)
+    fn select(&self, items: Vec<String>, query: &str) -> std::io::Result<Option<String>> {
+        Ok(run("sk", items, query, false)?.pop())
+    }
+
+    fn select_multi(&self, items: Vec<String>, query: &str) -> std::io::Result<Vec<String>> {
+        run("sk", items, query, true)
     }
 }
 
-fn run(command: &str, items: Vec<String>) -> std::io::Result<Option<String>> {
-    let mut selector = Command::new(command)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
+fn run(
+    command: &str,
+    items: Vec<String>,
+    query: &str,
+    multi: bool,
+) -> std::io::Result<Vec<String>> {
+    let mut cmd = Command::new(command);
+    cmd.arg("--query").arg(query);
+    if multi {
+        cmd.arg("--multi");
+    }
+    let mut selector = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
 
     let mut stdin = selector.stdin.as_mut().unwrap();
     for item in items {
@@ -31,8 +46,11 @@ fn run(command: &str, items: Vec<String>) -> std::io::Result<Option<String>> {
     }
 
     let output = selector.wait_with_output()?;
-    let select = (!output.stdout.is_empty())
-        .then_some(String::from_utf8_lossy(output.stdout.trim_ascii()).to_string());
+    let select = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
 
     Ok(select)
 }
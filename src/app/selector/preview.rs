@@ -0,0 +1,116 @@
+use std::{fs, path::PathBuf};
+
+use crate::app::{config::PreviewPosition, render};
+
+pub struct Preview {
+    pub dir: PathBuf,
+    pub markdown: bool,
+    pub position: PreviewPosition,
+    // 画面に対する割合（%）
+    pub width: u16,
+}
+
+impl Preview {
+    // widthに収まるように整形した行
+    pub fn lines(&self, item: &str, width: usize) -> Vec<String> {
+        let content = match fs::read_to_string(self.dir.join(item)) {
+            Ok(content) => content,
+            Err(e) => return vec![format!("cannot read {}: {}", item, e)],
+        };
+
+        if self.markdown {
+            return render::markdown_lines(&render::memo_text(item, content), width);
+        }
+
+        content
+            .lines()
+            .map(|line| line.replace('\t', "    ").chars().take(width).collect())
+            .collect()
+    }
+
+    // 行番号付きのそのままの内容。line（1始まり）を反転表示する
+    pub fn lines_at(&self, path: &str, line: usize, width: usize) -> Vec<String> {
+        let content = match fs::read_to_string(self.dir.join(path)) {
+            Ok(content) => content,
+            Err(e) => return vec![format!("cannot read {}: {}", path, e)],
+        };
+
+        content
+            .lines()
+            .enumerate()
+            .map(|(i, text)| {
+                let text: String = format!("{:>4} {}", i + 1, text.replace('\t', "    "))
+                    .chars()
+                    .take(width)
+                    .collect();
+                if i + 1 == line {
+                    format!("\x1b[7m{}", text)
+                } else {
+                    text
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+// nのp%。u16のまま掛けると広い端末で溢れる
+fn percent(n: u16, p: u16) -> u16 {
+    let value = u32::from(n) * u32::from(p) / 100;
+    u16::try_from(value).unwrap_or(n).min(n)
+}
+
+// 一覧とpreviewの枠。previewが狭すぎる時は一覧だけにする
+pub fn layout(cols: u16, rows: u16, preview: Option<&Preview>) -> (Rect, Option<Rect>) {
+    let full = Rect {
+        x: 0,
+        y: 0,
+        width: cols,
+        height: rows,
+    };
+    let Some(preview) = preview else {
+        return (full, None);
+    };
+
+    match preview.position {
+        PreviewPosition::Right => {
+            let width = percent(cols, preview.width);
+            if width < 20 || cols - width < 20 {
+                return (full, None);
+            }
+            let list = Rect {
+                width: cols - width,
+                ..full
+            };
+            let preview = Rect {
+                x: cols - width,
+                width,
+                ..full
+            };
+            (list, Some(preview))
+        }
+        PreviewPosition::Bottom => {
+            let height = percent(rows, preview.width);
+            if height < 5 || rows - height < 8 {
+                return (full, None);
+            }
+            let list = Rect {
+                height: rows - height,
+                ..full
+            };
+            let preview = Rect {
+                y: rows - height,
+                height,
+                ..full
+            };
+            (list, Some(preview))
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::{path::Path, str::FromStr};
+
+use chrono::{DateTime, Local};
+
+use crate::app::error::{MmemoError, MmemoResult};
+
+#[derive(Debug, Clone, Copy)]
+pub enum SlugKind {
+    // 空白を`_`にするだけで日本語などはそのまま
+    Unicode,
+    // アクセント付き文字をASCIIに寄せて、それ以外の非ASCII文字は落とす
+    Ascii,
+    // `2026-10-18-title.md`
+    Date,
+    // `20261018120000.md`
+    Timestamp,
+}
+
+pub struct ParseSlugKindError;
+
+impl FromStr for SlugKind {
+    type Err = ParseSlugKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unicode" | "keep-unicode" => Ok(SlugKind::Unicode),
+            "ascii" => Ok(SlugKind::Ascii),
+            "date" | "date-prefixed" => Ok(SlugKind::Date),
+            "timestamp" => Ok(SlugKind::Timestamp),
+            _ => Err(ParseSlugKindError),
+        }
+    }
+}
+
+// Windowsで使えないファイル名
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// titleからmemo_dirからの相対パスを作る。`/`はサブディレクトリの区切りとして扱う
+pub fn filename(title: &str, kind: SlugKind, now: DateTime<Local>) -> MmemoResult<String> {
+    let invalid = |reason: &str| MmemoError::InvalidArgs {
+        message: format!("invalid title {:?}: {}", title, reason),
+    };
+
+    let title = title.trim();
+    if title.starts_with(['/', '\\']) {
+        return Err(invalid("absolute paths are not allowed"));
+    }
+
+    let segments: Vec<&str> = title.split(['/', '\\']).map(str::trim).collect();
+    if segments.iter().any(|s| *s == "." || *s == "..") {
+        return Err(invalid("`.` and `..` are not allowed"));
+    }
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(invalid("empty path component"));
+    }
+
+    let (name, dirs) = segments
+        .split_last()
+        .ok_or_else(|| invalid("empty title"))?;
+
+    let mut path = Vec::new();
+    for dir in dirs {
+        let dir = slugify(dir, kind);
+        if dir.is_empty() {
+            return Err(invalid("empty directory name"));
+        }
+        path.push(dir);
+    }
+
+    let (stem, extension) = split_extension(name);
+    let stem = match kind {
+        SlugKind::Unicode | SlugKind::Ascii => slugify(stem, kind),
+        SlugKind::Date => {
+            let date = now.format("%Y-%m-%d").to_string();
+            match slugify(stem, SlugKind::Unicode) {
+                s if s.is_empty() => date,
+                s => format!("{}-{}", date, s),
+            }
+        }
+        SlugKind::Timestamp => now.format("%Y%m%d%H%M%S").to_string(),
+    };
+    // 全部落ちた時（ASCIIで日本語だけのtitleなど）はtimestampにする
+    let stem = if stem.is_empty() {
+        now.format("%Y%m%d%H%M%S").to_string()
+    } else {
+        stem
+    };
+
+    path.push(format!("{}.{}", stem, extension.unwrap_or("md")));
+    Ok(path.join("/"))
+}
+
+// 英字だけの短い拡張子なら残す（`v1.2`や`Mr. Smith`は拡張子扱いしない）
+fn split_extension(name: &str) -> (&str, Option<&str>) {
+    match Path::new(name).extension().and_then(|e| e.to_str()) {
+        Some(ext)
+            if !ext.is_empty()
+                && ext.len() <= 8
+                && ext.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            (&name[..name.len() - ext.len() - 1], Some(ext))
+        }
+        _ => (name, None),
+    }
+}
+
+fn slugify(segment: &str, kind: SlugKind) -> String {
+    let mut slug = String::new();
+    for c in segment.chars() {
+        match c {
+            c if c.is_control() => {}
+            c if c.is_whitespace() => slug.push('_'),
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => slug.push('-'),
+            c if c.is_ascii() => slug.push(c),
+            c => match kind {
+                SlugKind::Ascii => slug.push_str(transliterate(c)),
+                _ => slug.push(c),
+            },
+        }
+    }
+
+    let slug = slug.trim_matches(['.', '_', '-', ' ']).to_string();
+    if RESERVED.iter().any(|r| r.eq_ignore_ascii_case(&slug)) {
+        return format!("{}_", slug);
+    }
+    slug
+}
+
+fn transliterate(c: char) -> &'static str {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' => "A",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'ç' | 'ć' | 'č' => "c",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ě' => "E",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' => "I",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' => "O",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ß' => "ss",
+        'ś' | 'š' => "s",
+        'Ś' | 'Š' => "S",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ů' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ł' => "l",
+        'Ł' => "L",
+        'đ' | 'ð' => "d",
+        'Đ' | 'Ð' => "D",
+        'þ' => "th",
+        'Þ' => "TH",
+        '–' | '—' => "-",
+        _ => "",
+    }
+}
+
+// 既にあれば`name-2.md`、`name-3.md`...にする
+pub fn unique(memo_dir: &Path, filename: &str) -> String {
+    if !memo_dir.join(filename).exists() {
+        return filename.to_string();
+    }
+
+    let (stem, extension) = match filename.rsplit_once('.') {
+        Some((stem, ext)) if !ext.contains('/') => (stem, format!(".{}", ext)),
+        _ => (filename, String::new()),
+    };
+    (2..)
+        .map(|n| format!("{}-{}{}", stem, n, extension))
+        .find(|candidate| !memo_dir.join(candidate).exists())
+        .unwrap_or_else(|| filename.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 18, 9, 30, 0).unwrap()
+    }
+
+    fn name(title: &str, kind: SlugKind) -> MmemoResult<String> {
+        filename(title, kind, now())
+    }
+
+    #[test]
+    fn rejects_paths_outside_memo_dir() {
+        for title in [
+            "../x", "a/../b", "a/./b", "./x", "/etc/x", "\\x", "a\\..\\b", "a//b", "a/", "a/ /b",
+        ] {
+            assert!(name(title, SlugKind::Unicode).is_err(), "{:?}", title);
+        }
+    }
+
+    #[test]
+    fn subdirectories() {
+        assert_eq!(
+            name("work/meeting notes", SlugKind::Unicode).unwrap(),
+            "work/meeting_notes.md"
+        );
+        assert_eq!(name("a\\b", SlugKind::Unicode).unwrap(), "a/b.md");
+    }
+
+    #[test]
+    fn reserved_names() {
+        assert_eq!(name("CON", SlugKind::Unicode).unwrap(), "CON_.md");
+        assert_eq!(name("con", SlugKind::Unicode).unwrap(), "con_.md");
+        assert_eq!(name("nul/x", SlugKind::Unicode).unwrap(), "nul_/x.md");
+    }
+
+    #[test]
+    fn invalid_characters() {
+        assert_eq!(name("a:b?c", SlugKind::Unicode).unwrap(), "a-b-c.md");
+        assert_eq!(name("a b\u{7}", SlugKind::Unicode).unwrap(), "a_b.md");
+        assert_eq!(name("a\tb", SlugKind::Unicode).unwrap(), "ab.md");
+        assert_eq!(name("..hidden..", SlugKind::Unicode).unwrap(), "hidden.md");
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(
+            name("日本語 メモ", SlugKind::Unicode).unwrap(),
+            "日本語_メモ.md"
+        );
+        assert_eq!(
+            name("Café crème", SlugKind::Ascii).unwrap(),
+            "Cafe_creme.md"
+        );
+        assert_eq!(
+            name("日本語", SlugKind::Ascii).unwrap(),
+            "20261018093000.md"
+        );
+        assert_eq!(name("todo", SlugKind::Date).unwrap(), "2026-10-18-todo.md");
+        assert_eq!(
+            name("work/todo", SlugKind::Timestamp).unwrap(),
+            "work/20261018093000.md"
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(name("notes.txt", SlugKind::Unicode).unwrap(), "notes.txt");
+        assert_eq!(name("v1.2", SlugKind::Unicode).unwrap(), "v1.2.md");
+    }
+
+    #[test]
+    fn unique_adds_suffix() {
+        let dir = env::temp_dir().join(format!("mmemo-slug-test-{}", process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+
+        assert_eq!(unique(&dir, "todo.md"), "todo.md");
+        fs::write(dir.join("todo.md"), "").unwrap();
+        assert_eq!(unique(&dir, "todo.md"), "todo-2.md");
+        fs::write(dir.join("todo-2.md"), "").unwrap();
+        assert_eq!(unique(&dir, "todo.md"), "todo-3.md");
+        fs::write(dir.join("sub/a.b.md"), "").unwrap();
+        assert_eq!(unique(&dir, "sub/a.b.md"), "sub/a.b-2.md");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
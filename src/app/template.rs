@@ -1,16 +1,377 @@
-use std::io::Read;
+use std::{collections::BTreeMap, env, fs, io::Read, path::Path};
 
-use crate::app::error::MmemoResult;
+use chrono::{DateTime, Local, format::Item, format::StrftimeItems};
 
-pub fn load_template(title: &str, mut file: impl Read) -> MmemoResult<String> {
+use crate::app::{
+    error::{MmemoError, MmemoResult},
+    memo,
+};
+
+// memo_templateが無い時にも使う
+pub const DEFAULT_TEMPLATE: &str = concat!(
+    "---\n",
+    "title: {{title}}\n",
+    "date: {{date}}\n",
+    "---\n",
+    "\n",
+    "# {{title}}\n"
+);
+
+// templates/にあるtemplateのファイル名（名前順）
+pub fn named_templates(dir: &Path) -> MmemoResult<Vec<String>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_file() && !name.starts_with('.') {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+// 拡張子なしの名前でもファイル名でも探す
+pub fn find_named(dir: &Path, name: &str) -> MmemoResult<String> {
+    named_templates(dir)?
+        .into_iter()
+        .find(|file| file == name || Path::new(file).file_stem().is_some_and(|stem| stem == name))
+        .ok_or_else(|| MmemoError::TemplateNotFound(name.to_string()))
+}
+
+// varsは[template.vars]と--varを合わせたもの（--varが優先）
+// nowは{{date}}や{{time}}に使う日時
+pub fn load_template(
+    title: &str,
+    mut file: impl Read,
+    vars: &BTreeMap<String, String>,
+    now: DateTime<Local>,
+) -> MmemoResult<String> {
     let mut buf = String::new();
     file.read_to_string(&mut buf)?;
 
-    let created_date = chrono::Utc::now().date_naive(); // "YYYY-MM-DD"
-    let content = buf
-        .replace("{{title}}", title)
-        .replace("{{date}}", &created_date.to_string())
-        .replace("{{tags}}", "tags: []");
+    render(&buf, title, vars, now)
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Placeholder { name: &'a str, line: usize },
+    If { name: &'a str, line: usize },
+    Else { line: usize },
+    EndIf { line: usize },
+}
+
+fn tokenize(template: &str) -> MmemoResult<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+    let mut line_count = 1;
+    // restが行頭から始まっているか
+    let mut at_line_start = true;
+
+    while let Some(start) = rest.find("{{") {
+        // `\{{`はそのまま`{{`として出す
+        if rest[..start].ends_with('\\') {
+            tokens.push(Token::Text(&rest[..start - 1]));
+            tokens.push(Token::Text("{{"));
+            line_count += rest[..start].matches('\n').count();
+            at_line_start = false;
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(end) = rest[start..].find("}}") else {
+            let line = line_count + rest[..start].matches('\n').count();
+            return Err(template_error(line, "unclosed {{"));
+        };
+        let end = start + end + 2;
+        let line = line_count + rest[..start].matches('\n').count();
+        let inner = rest[start + 2..end - 2].trim();
+        let mut text = &rest[..start];
+        let mut next = end;
+
+        let token = if let Some(name) = inner.strip_prefix("#if") {
+            Token::If {
+                name: name.trim(),
+                line,
+            }
+        } else if inner == "else" {
+            Token::Else { line }
+        } else if inner == "/if" {
+            Token::EndIf { line }
+        } else {
+            Token::Placeholder { name: inner, line }
+        };
+
+        // {{#if}}などだけの行は行ごと消す
+        if !matches!(token, Token::Placeholder { .. }) {
+            let line_start = text.rfind('\n').map(|i| i + 1);
+            let before = &text[line_start.unwrap_or(0)..];
+            let after = &rest[end..];
+            let after_len = after.find('\n').map(|i| i + 1).unwrap_or(after.len());
+            if (line_start.is_some() || at_line_start)
+                && before.trim().is_empty()
+                && after[..after_len].trim().is_empty()
+            {
+                text = &text[..line_start.unwrap_or(0)];
+                next = end + after_len;
+            }
+        }
+
+        tokens.push(Token::Text(text));
+        tokens.push(token);
+
+        line_count += rest[..next].matches('\n').count();
+        at_line_start = rest[..next].ends_with('\n');
+        rest = &rest[next..];
+    }
+    tokens.push(Token::Text(rest));
+
+    Ok(tokens)
+}
+
+fn render(
+    template: &str,
+    title: &str,
+    vars: &BTreeMap<String, String>,
+    now: DateTime<Local>,
+) -> MmemoResult<String> {
+    let mut out = String::with_capacity(template.len());
+
+    // (この{{#if}}の条件, {{else}}の後か)
+    let mut blocks: Vec<(bool, bool)> = Vec::new();
+    let active = |blocks: &[(bool, bool)]| blocks.iter().all(|(cond, in_else)| cond != in_else);
+
+    for token in tokenize(template)? {
+        match token {
+            Token::Text(text) => {
+                if active(&blocks) {
+                    out.push_str(text);
+                }
+            }
+            Token::If { name, line } => {
+                if name.is_empty() {
+                    return Err(template_error(line, "{{#if}} needs a variable name"));
+                }
+                let cond = vars
+                    .get(name)
+                    .is_some_and(|v| !v.is_empty() && v != "false");
+                blocks.push((cond, false));
+            }
+            Token::Else { line } => match blocks.last_mut() {
+                Some((_, in_else)) if !*in_else => *in_else = true,
+                _ => return Err(template_error(line, "{{else}} without {{#if}}")),
+            },
+            Token::EndIf { line } => {
+                if blocks.pop().is_none() {
+                    return Err(template_error(line, "{{/if}} without {{#if}}"));
+                }
+            }
+            Token::Placeholder { name, line } => {
+                if !active(&blocks) {
+                    continue;
+                }
+                let value = match name.split_once(':') {
+                    Some(("date" | "time", format)) => format_now(&now, format, line)?,
+                    Some(("env", key)) => env::var(key).map_err(|_| {
+                        template_error(line, &format!("environment variable {} is not set", key))
+                    })?,
+                    _ => match name {
+                        // front matterの`title:`ではparseできるように囲む
+                        "title" if in_front_matter_value(&out, "title") => memo::quote_value(title),
+                        "title" => title.to_string(),
+                        "date" => now.format("%Y-%m-%d").to_string(),
+                        "time" => now.format("%H:%M").to_string(),
+                        "uuid" => uuid::Uuid::new_v4().to_string(),
+                        _ => match vars.get(name) {
+                            Some(value) => value.clone(),
+                            // 以前からある{{tags}}は空のtags行にする
+                            None if name == "tags" => "tags: []".to_string(),
+                            None => {
+                                return Err(template_error(
+                                    line,
+                                    &format!("unknown placeholder {{{{{}}}}}", name),
+                                ));
+                            }
+                        },
+                    },
+                };
+                out.push_str(&value);
+            }
+        }
+    }
+
+    if !blocks.is_empty() {
+        return Err(MmemoError::Parse {
+            message: "template: {{#if}} is not closed".to_string(),
+        });
+    }
+
+    Ok(out)
+}
+
+// outの最後の行がfront matterの中の`key:`だけか
+fn in_front_matter_value(out: &str, key: &str) -> bool {
+    let mut lines = out.split('\n');
+    if lines.next().map(str::trim_end) != Some("---") {
+        return false;
+    }
+    let lines: Vec<&str> = lines.collect();
+    let Some((last, before)) = lines.split_last() else {
+        return false;
+    };
+    before.iter().all(|line| line.trim_end() != "---")
+        && last
+            .split_once(':')
+            .is_some_and(|(k, v)| k == key && v.trim().is_empty())
+}
+
+// 不正な書式はchronoがpanicするので先に確かめる
+fn format_now(now: &DateTime<Local>, format: &str, line: usize) -> MmemoResult<String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(template_error(
+            line,
+            &format!("invalid date format {}", format),
+        ));
+    }
+    Ok(now.format(format).to_string())
+}
+
+fn template_error(line: usize, message: &str) -> MmemoError {
+    MmemoError::Parse {
+        message: format!("template line {}: {}", line, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 18, 9, 30, 0).unwrap()
+    }
+
+    fn vars(list: &[(&str, &str)]) -> BTreeMap<String, String> {
+        list.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn run(template: &str, list: &[(&str, &str)]) -> MmemoResult<String> {
+        render(template, "Title", &vars(list), now())
+    }
+
+    fn error(template: &str) -> String {
+        run(template, &[]).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn placeholders() {
+        let out = run(
+            "{{title}} {{ date }} {{time}} {{date:%d/%m}} {{who}} {{tags}}",
+            &[("who", "me")],
+        )
+        .unwrap();
+        assert_eq!(out, "Title 2026-10-18 09:30 18/10 me tags: []");
+        assert_eq!(run("{{uuid}}", &[]).unwrap().len(), 36);
+    }
+
+    #[test]
+    fn conditionals_remove_their_lines() {
+        let template = "a\n{{#if x}}\nyes\n{{else}}\nno\n{{/if}}\nb\n";
+        assert_eq!(run(template, &[("x", "1")]).unwrap(), "a\nyes\nb\n");
+        assert_eq!(run(template, &[]).unwrap(), "a\nno\nb\n");
+        // 空と"false"は偽
+        assert_eq!(run(template, &[("x", "false")]).unwrap(), "a\nno\nb\n");
+        assert_eq!(run(template, &[("x", "")]).unwrap(), "a\nno\nb\n");
+        // 行の途中ならその部分だけ
+        assert_eq!(
+            run("[{{#if x}}on{{else}}off{{/if}}]\n", &[]).unwrap(),
+            "[off]\n"
+        );
+        // 先頭の行
+        assert_eq!(
+            run("{{#if x}}\ny\n{{/if}}\nz", &[("x", "1")]).unwrap(),
+            "y\nz"
+        );
+    }
+
+    #[test]
+    fn nested_conditionals() {
+        let template = "{{#if a}}\nA\n{{#if b}}\nAB\n{{else}}\nA!B\n{{/if}}\n{{else}}\n{{#if b}}\n!AB\n{{/if}}\n{{/if}}\n";
+        assert_eq!(run(template, &[("a", "1"), ("b", "1")]).unwrap(), "A\nAB\n");
+        assert_eq!(run(template, &[("a", "1")]).unwrap(), "A\nA!B\n");
+        assert_eq!(run(template, &[("b", "1")]).unwrap(), "!AB\n");
+        assert_eq!(run(template, &[]).unwrap(), "");
+        // 使わない方のplaceholderはエラーにしない
+        assert_eq!(run("{{#if a}}{{nope}}{{/if}}", &[]).unwrap(), "");
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        assert_eq!(
+            error("a\nb {{title"),
+            "Parse error: template line 2: unclosed {{"
+        );
+        assert_eq!(
+            error("a\n\n{{nope}}"),
+            "Parse error: template line 3: unknown placeholder {{nope}}"
+        );
+        assert_eq!(
+            error("x\n{{else}}"),
+            "Parse error: template line 2: {{else}} without {{#if}}"
+        );
+        assert_eq!(
+            error("{{#if a}}\n{{else}}\n{{else}}\n{{/if}}"),
+            "Parse error: template line 3: {{else}} without {{#if}}"
+        );
+        assert_eq!(
+            error("\n\n\n{{/if}}"),
+            "Parse error: template line 4: {{/if}} without {{#if}}"
+        );
+        assert_eq!(
+            error("{{#if}}"),
+            "Parse error: template line 1: {{#if}} needs a variable name"
+        );
+        assert_eq!(
+            error("{{#if a}}\n"),
+            "Parse error: template: {{#if}} is not closed"
+        );
+        assert_eq!(
+            error("\n{{date:%Q}}"),
+            "Parse error: template line 2: invalid date format %Q"
+        );
+        assert!(error("{{env:MMEMO_TEST_SURELY_UNSET}}").contains("is not set"));
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(
+            run("\\{{title}} {{title}}", &[]).unwrap(),
+            "{{title}} Title"
+        );
+        assert_eq!(run("a\\{{\n{{title}}", &[]).unwrap(), "a{{\nTitle");
+        // 行番号は数え続ける
+        assert_eq!(
+            error("\\{{\n\n{{nope}}"),
+            "Parse error: template line 3: unknown placeholder {{nope}}"
+        );
+    }
 
-    Ok(content)
+    #[test]
+    fn title_is_quoted_only_in_front_matter() {
+        let template = "---\ntitle: {{title}}\nalias: {{title}}\n---\n# {{title}}\n";
+        let out = render(template, "[WIP] a: b", &BTreeMap::new(), now()).unwrap();
+        assert_eq!(
+            out,
+            "---\ntitle: \"[WIP] a: b\"\nalias: [WIP] a: b\n---\n# [WIP] a: b\n"
+        );
+        // front matterの外の`title:`はそのまま
+        let out = render("x\ntitle: {{title}}\n", "[x]", &BTreeMap::new(), now()).unwrap();
+        assert_eq!(out, "x\ntitle: [x]\n");
+    }
 }
@@ -0,0 +1,258 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use toml::Table;
+
+use crate::app::error::{MmemoError, MmemoResult};
+
+// memo_dir直下。dotで始まるのでdir_filesには出てこない
+const TRASH_DIR: &str = ".trash";
+
+#[derive(Debug)]
+pub struct TrashEntry {
+    pub id: String,
+    // memo_dirからの元の相対パス
+    pub path: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TrashEntry {
+    pub fn deleted_at_local(&self) -> String {
+        self.deleted_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
+
+// 削除したmemoの本体を置く場所
+pub fn files_dir(memo_dir: &Path) -> PathBuf {
+    memo_dir.join(TRASH_DIR).join("files")
+}
+
+fn info_dir(memo_dir: &Path) -> PathBuf {
+    memo_dir.join(TRASH_DIR).join("info")
+}
+
+fn info_path(memo_dir: &Path, id: &str) -> PathBuf {
+    info_dir(memo_dir).join(format!("{}.toml", id))
+}
+
+// memoをtrashに移してidを返す
+pub fn trash(memo_dir: &Path, path: &str) -> MmemoResult<String> {
+    let files = files_dir(memo_dir);
+    let info = info_dir(memo_dir);
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let now = Utc::now();
+    let name = Path::new(path)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    let base = format!("{}-{}", now.format("%Y%m%d%H%M%S"), name);
+
+    let mut id = base.clone();
+    let mut n = 1;
+    while files.join(&id).exists() || info_path(memo_dir, &id).exists() {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+
+    let mut table = Table::new();
+    table.insert("path".into(), path.into());
+    table.insert("deleted_at".into(), now.to_rfc3339().into());
+    fs::write(info_path(memo_dir, &id), table.to_string())?;
+
+    let from = memo_dir.join(path);
+    if let Err(e) = fs::rename(&from, files.join(&id)) {
+        let _ = fs::remove_file(info_path(memo_dir, &id));
+        return Err(e.into());
+    }
+    // mvと同じく空になったサブディレクトリは消す
+    if let Some(parent) = from.parent()
+        && parent != memo_dir
+    {
+        let _ = fs::remove_dir(parent);
+    }
+
+    Ok(id)
+}
+
+// 削除日時の新しい順
+pub fn entries(memo_dir: &Path) -> MmemoResult<Vec<TrashEntry>> {
+    let info = info_dir(memo_dir);
+    if !info.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(info)? {
+        let entry_path = entry?.path();
+        let Some(id) = entry_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.strip_suffix(".toml"))
+        else {
+            continue;
+        };
+        // 壊れたinfoが1つあっても他は扱えるようにする
+        match read_entry(&entry_path, id) {
+            Ok(e) => entries.push(e),
+            Err(e) => eprintln!("warning: skipping trash entry {}: {}", id, e),
+        }
+    }
+
+    entries.sort_by_key(|e| std::cmp::Reverse(e.deleted_at));
+    Ok(entries)
+}
+
+fn read_entry(info_path: &Path, id: &str) -> MmemoResult<TrashEntry> {
+    let parse_error = || MmemoError::Parse {
+        message: format!("broken trash info: {}", info_path.display()),
+    };
+
+    let table: Table = fs::read_to_string(info_path)?
+        .parse()
+        .map_err(|_| parse_error())?;
+    let path = table
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(parse_error)?;
+    let deleted_at = table
+        .get("deleted_at")
+        .and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .ok_or_else(parse_error)?;
+
+    Ok(TrashEntry {
+        id: id.to_string(),
+        path: path.to_string(),
+        deleted_at: deleted_at.with_timezone(&Utc),
+    })
+}
+
+// 元の場所に戻す。既にmemoがあれば上書きしない
+pub fn restore(memo_dir: &Path, entry: &TrashEntry) -> MmemoResult<()> {
+    let target = memo_dir.join(&entry.path);
+    if target.exists() {
+        return Err(MmemoError::InvalidArgs {
+            message: format!("{} already exists", entry.path),
+        });
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(files_dir(memo_dir).join(&entry.id), target)?;
+    fs::remove_file(info_path(memo_dir, &entry.id))?;
+
+    Ok(())
+}
+
+// older_thanより前に削除したもの。Noneなら全部
+pub fn expired(memo_dir: &Path, older_than: Option<TimeDelta>) -> MmemoResult<Vec<TrashEntry>> {
+    let now = Utc::now();
+    let entries = entries(memo_dir)?
+        .into_iter()
+        .filter(|entry| older_than.is_none_or(|d| now - entry.deleted_at >= d))
+        .collect();
+    Ok(entries)
+}
+
+// 完全に消す
+pub fn empty(memo_dir: &Path, entries: &[TrashEntry]) -> MmemoResult<()> {
+    for entry in entries {
+        let file = files_dir(memo_dir).join(&entry.id);
+        if file.exists() {
+            fs::remove_file(file)?;
+        }
+        fs::remove_file(info_path(memo_dir, &entry.id))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn memo_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mmemo-trash-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn trash_and_restore() {
+        let dir = memo_dir("restore");
+        fs::write(dir.join("sub/a.md"), "one").unwrap();
+
+        let id = trash(&dir, "sub/a.md").unwrap();
+        assert!(!dir.join("sub/a.md").exists());
+        // 空になったサブディレクトリは残らない
+        assert!(!dir.join("sub").exists());
+
+        let entries = entries(&dir).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, id);
+        assert_eq!(entries[0].path, "sub/a.md");
+
+        restore(&dir, &entries[0]).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("sub/a.md")).unwrap(), "one");
+        assert!(super::entries(&dir).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn trash_same_name() {
+        let dir = memo_dir("collision");
+        fs::write(dir.join("a.md"), "one").unwrap();
+        let first = trash(&dir, "a.md").unwrap();
+        fs::write(dir.join("a.md"), "two").unwrap();
+        let second = trash(&dir, "a.md").unwrap();
+        assert_ne!(first, second);
+
+        let entries = entries(&dir).unwrap();
+        assert_eq!(entries.len(), 2);
+        let first = entries.iter().find(|e| e.id == first).unwrap();
+        let second = entries.iter().find(|e| e.id == second).unwrap();
+
+        restore(&dir, second).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.md")).unwrap(), "two");
+        // 戻す先が埋まっていれば上書きしない
+        assert!(restore(&dir, first).is_err());
+        assert_eq!(fs::read_to_string(dir.join("a.md")).unwrap(), "two");
+
+        fs::remove_file(dir.join("a.md")).unwrap();
+        restore(&dir, first).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.md")).unwrap(), "one");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn broken_info_is_skipped() {
+        let dir = memo_dir("broken");
+        fs::write(dir.join("a.md"), "one").unwrap();
+        let id = trash(&dir, "a.md").unwrap();
+        fs::write(info_dir(&dir).join("broken.toml"), "path = ").unwrap();
+        fs::write(info_dir(&dir).join("missing.toml"), "path = \"b.md\"").unwrap();
+
+        let entries = entries(&dir).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, id);
+
+        empty(&dir, &entries).unwrap();
+        assert!(!files_dir(&dir).join(&id).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,560 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Write, stderr},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::app::{
+    commands::{self, MovePlan},
+    config::{Config, PreviewKind},
+    error::MmemoResult,
+    memo::{self, Memo},
+    selector::{
+        builtin::{draw_count, draw_input, draw_items, draw_outline, draw_preview},
+        core::{MatchResult, Matcher},
+        preview::{Preview, layout},
+    },
+    trash,
+};
+
+// 他のところで変更されたmemoを拾うために読み直す間隔
+const RELOAD_INTERVAL: Duration = Duration::from_secs(2);
+
+const KEYS: &str = "enter edit  ^o new  ^r rename  ^t tags  ^x delete  ^l reload  esc quit";
+
+enum Mode {
+    // 入力で一覧を絞り込む
+    Filter,
+    // 下の行に文字列を入力する
+    Prompt { action: Action, input: String },
+    ConfirmDelete(String),
+    // 他のmemoのリンクも書き換える時は確認する
+    ConfirmRename(Box<MovePlan>),
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    New,
+    Rename,
+    Tags,
+}
+
+impl Action {
+    fn label(self) -> &'static str {
+        match self {
+            Action::New => "New memo title: ",
+            Action::Rename => "Rename to: ",
+            Action::Tags => "Tags (comma separated): ",
+        }
+    }
+}
+
+struct Tui<'a> {
+    config: &'a Config,
+    memo_dir: PathBuf,
+    preview: Option<Preview>,
+    matcher: Matcher,
+    query: String,
+    results: Vec<MatchResult>,
+    selected: usize,
+    offset: usize,
+    // 最後に読み直した時のディレクトリの更新日時。memoの追加・削除・名前の変更で変わる
+    dirs: Vec<(PathBuf, Option<SystemTime>)>,
+    // (path, 更新日時, 描画済みの行)
+    preview_cache: Option<(String, Option<SystemTime>, Vec<String>)>,
+    preview_scroll: usize,
+    mode: Mode,
+    // 最後に行った操作の結果やエラー
+    status: String,
+}
+
+// 終了するまで一覧に戻ってくる。エディタなどを開く間は画面を元に戻す
+pub fn run(config: &Config, memo_dir: &Path) -> MmemoResult<()> {
+    let markdown = match config.preview {
+        PreviewKind::Markdown => Some(true),
+        PreviewKind::Raw => Some(false),
+        PreviewKind::None => None,
+    };
+    let mut tui = Tui {
+        config,
+        memo_dir: memo_dir.to_path_buf(),
+        preview: markdown.map(|markdown| Preview {
+            dir: memo_dir.to_path_buf(),
+            markdown,
+            position: config.preview_position,
+            width: config.preview_width,
+        }),
+        matcher: Matcher::new(Vec::new()),
+        query: String::new(),
+        results: Vec::new(),
+        selected: 0,
+        offset: 0,
+        dirs: Vec::new(),
+        preview_cache: None,
+        preview_scroll: 0,
+        mode: Mode::Filter,
+        status: String::new(),
+    };
+    tui.reload(None)?;
+
+    enter()?;
+    let result = tui.event_loop();
+    leave()?;
+    result
+}
+
+fn enter() -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    execute!(stderr(), EnterAlternateScreen, Hide)
+}
+
+fn leave() -> io::Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(stderr(), LeaveAlternateScreen, Show)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// memo_dirと、その下のdotで始まらないディレクトリの更新日時
+fn memo_dirs(memo_dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut dirs = vec![(memo_dir.to_path_buf(), modified(memo_dir))];
+    let mut i = 0;
+    while i < dirs.len() {
+        if let Ok(entries) = fs::read_dir(&dirs[i].0) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !entry.file_name().to_string_lossy().starts_with('.') && path.is_dir() {
+                    let time = modified(&path);
+                    dirs.push((path, time));
+                }
+            }
+        }
+        i += 1;
+    }
+    dirs
+}
+
+impl Tui<'_> {
+    fn event_loop(&mut self) -> MmemoResult<()> {
+        let mut last_reload = Instant::now();
+        let mut redraw = true;
+        loop {
+            if redraw {
+                self.draw()?;
+                redraw = false;
+            }
+
+            if !event::poll(Duration::from_millis(100))? {
+                if last_reload.elapsed() >= RELOAD_INTERVAL && matches!(self.mode, Mode::Filter) {
+                    redraw = self.refresh()?;
+                    last_reload = Instant::now();
+                }
+                continue;
+            }
+
+            match event::read()? {
+                Event::Key(key) => {
+                    if !self.handle_key(key)? {
+                        return Ok(());
+                    }
+                    redraw = true;
+                }
+                Event::Resize(..) => {
+                    self.preview_cache = None;
+                    redraw = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // 一覧か表示中のmemoが変わっていれば読み直す。描き直す必要があればtrue
+    // 全体を辿り直さないように、前回見たディレクトリの更新日時だけを調べる
+    fn refresh(&mut self) -> MmemoResult<bool> {
+        let dirs_changed = self.dirs.iter().any(|(dir, time)| modified(dir) != *time);
+        let preview_changed = self
+            .preview_cache
+            .as_ref()
+            .is_some_and(|(path, time, _)| modified(&self.memo_dir.join(path)) != *time);
+        if !dirs_changed && !preview_changed {
+            return Ok(false);
+        }
+        self.reload(None)?;
+        Ok(true)
+    }
+
+    // selectかカーソル位置のmemoを選んだまま一覧を読み直す
+    fn reload(&mut self, select: Option<&str>) -> MmemoResult<()> {
+        let keep = select.map(str::to_string).or_else(|| self.current());
+        // 一覧より先に調べておけば、その間の変更は次のrefreshで拾える
+        self.dirs = memo_dirs(&self.memo_dir);
+        self.matcher = Matcher::new(commands::memo_files(&self.memo_dir, &[])?);
+        self.filter(keep.as_deref());
+        Ok(())
+    }
+
+    fn filter(&mut self, keep: Option<&str>) {
+        self.results = self.matcher.fuzzy_match(&self.query);
+        match keep.and_then(|keep| self.results.iter().position(|r| r.item == keep)) {
+            Some(i) => self.selected = i,
+            None => self.selected = self.selected.min(self.results.len().saturating_sub(1)),
+        }
+    }
+
+    fn current(&self) -> Option<String> {
+        self.results.get(self.selected).map(|r| r.item.clone())
+    }
+
+    // falseなら終了する
+    fn handle_key(&mut self, key: KeyEvent) -> MmemoResult<bool> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('c') {
+            return Ok(false);
+        }
+
+        match &mut self.mode {
+            Mode::Prompt { action, input } => match key.code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Filter;
+                    self.status = "Cancelled".to_string();
+                }
+                KeyCode::Enter => {
+                    let (action, input) = (*action, input.trim().to_string());
+                    self.mode = Mode::Filter;
+                    if let Err(e) = self.apply(action, &input) {
+                        self.status = e.to_string();
+                    }
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) if !ctrl => input.push(c),
+                _ => {}
+            },
+            Mode::ConfirmDelete(file) => {
+                let file = file.clone();
+                self.mode = Mode::Filter;
+                match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        let deleted =
+                            trash::trash(&self.memo_dir, &file).and_then(|_| self.reload(None));
+                        self.status = match deleted {
+                            Ok(()) => format!("Moved to trash: {}", file),
+                            Err(e) => e.to_string(),
+                        };
+                    }
+                    _ => self.status = "Cancelled".to_string(),
+                }
+            }
+            Mode::ConfirmRename(_) => {
+                let mode = std::mem::replace(&mut self.mode, Mode::Filter);
+                match (mode, key.code) {
+                    (Mode::ConfirmRename(plan), KeyCode::Char('y') | KeyCode::Char('Y')) => {
+                        if let Err(e) = self.rename(&plan) {
+                            self.status = e.to_string();
+                        }
+                    }
+                    _ => self.status = "Cancelled".to_string(),
+                }
+            }
+            Mode::Filter => return self.handle_filter_key(key, ctrl),
+        }
+        Ok(true)
+    }
+
+    fn handle_filter_key(&mut self, key: KeyEvent, ctrl: bool) -> MmemoResult<bool> {
+        let current = self.current();
+        self.status.clear();
+        match key.code {
+            KeyCode::Esc if self.query.is_empty() => return Ok(false),
+            KeyCode::Esc => {
+                self.query.clear();
+                self.filter(current.as_deref());
+            }
+            KeyCode::Enter => {
+                if let Some(file) = current {
+                    let config = self.config;
+                    let memo_dir = self.memo_dir.clone();
+                    self.suspend(|| {
+                        process::Command::new(&config.editor)
+                            .current_dir(&memo_dir)
+                            .arg(&file)
+                            .status()?;
+                        Ok(())
+                    })?;
+                    self.reload(Some(&file))?;
+                }
+            }
+            KeyCode::Char('o') if ctrl => {
+                self.mode = Mode::Prompt {
+                    action: Action::New,
+                    input: String::new(),
+                };
+            }
+            KeyCode::Char('r') if ctrl => {
+                if let Some(file) = current {
+                    // 同じディレクトリのまま名前だけ変えられるように`dir/title`を入れておく
+                    let title = Memo::load(&self.memo_dir, &file)
+                        .map(|memo| memo.title())
+                        .unwrap_or_default();
+                    let input = match file.rsplit_once('/') {
+                        Some((dir, _)) => format!("{}/{}", dir, title),
+                        None => title,
+                    };
+                    self.mode = Mode::Prompt {
+                        action: Action::Rename,
+                        input,
+                    };
+                }
+            }
+            KeyCode::Char('t') if ctrl => {
+                if let Some(file) = current {
+                    let tags = Memo::load(&self.memo_dir, &file)
+                        .ok()
+                        .and_then(|memo| memo.front_matter)
+                        .map(|fm| fm.tags.join(", "))
+                        .unwrap_or_default();
+                    self.mode = Mode::Prompt {
+                        action: Action::Tags,
+                        input: tags,
+                    };
+                }
+            }
+            KeyCode::Char('x') if ctrl => {
+                if let Some(file) = current {
+                    self.mode = Mode::ConfirmDelete(file);
+                }
+            }
+            KeyCode::Delete => {
+                if let Some(file) = current {
+                    self.mode = Mode::ConfirmDelete(file);
+                }
+            }
+            KeyCode::Char('l') if ctrl => {
+                self.reload(None)?;
+                self.status = format!("Reloaded {} memos", self.matcher.items.len());
+            }
+            KeyCode::Char('n') if ctrl => self.move_cursor(1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('p') if ctrl => self.move_cursor(-1),
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Char('d') if ctrl => self.scroll_preview(true),
+            KeyCode::PageDown => self.scroll_preview(true),
+            KeyCode::Char('u') if ctrl => self.scroll_preview(false),
+            KeyCode::PageUp => self.scroll_preview(false),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter(None);
+                self.selected = 0;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.filter(None);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    // 端は反対側へ回る
+    fn move_cursor(&mut self, step: isize) {
+        let len = self.results.len();
+        if len == 0 {
+            return;
+        }
+        self.selected = (self.selected as isize + step).rem_euclid(len as isize) as usize;
+    }
+
+    fn scroll_preview(&mut self, down: bool) {
+        let Ok((cols, rows)) = terminal::size() else {
+            return;
+        };
+        let (_, Some(area)) = layout(cols, rows, self.preview.as_ref()) else {
+            return;
+        };
+        let step = (area.height as usize / 2).max(1);
+        self.preview_scroll = match (down, &self.preview_cache) {
+            (true, Some((_, _, lines))) => {
+                (self.preview_scroll + step).min(lines.len().saturating_sub(1))
+            }
+            (true, None) => self.preview_scroll,
+            (false, _) => self.preview_scroll.saturating_sub(step),
+        };
+    }
+
+    fn apply(&mut self, action: Action, input: &str) -> MmemoResult<()> {
+        if input.is_empty() && !matches!(action, Action::Tags) {
+            self.status = "Cancelled".to_string();
+            return Ok(());
+        }
+
+        match action {
+            Action::New => {
+                let before: BTreeSet<String> = self.matcher.items.iter().cloned().collect();
+                let config = self.config;
+                self.suspend(|| commands::new(config, input, None, &[], false))?;
+                self.reload(None)?;
+                // 変更せずに閉じてremove_unchangedで消えた時は何も増えない
+                let created = self.matcher.items.iter().find(|f| !before.contains(*f));
+                match created.cloned() {
+                    Some(file) => {
+                        self.query.clear();
+                        self.reload(Some(&file))?;
+                        self.status = format!("Created: {}", file);
+                    }
+                    None => self.status = "No memo created".to_string(),
+                }
+            }
+            Action::Rename => {
+                let Some(from) = self.current() else {
+                    return Ok(());
+                };
+                let plan = commands::plan_move(self.config, &self.memo_dir, &from, input, true)?;
+                if plan.others().next().is_some() {
+                    self.mode = Mode::ConfirmRename(Box::new(plan));
+                } else {
+                    self.rename(&plan)?;
+                }
+            }
+            Action::Tags => {
+                let Some(file) = self.current() else {
+                    return Ok(());
+                };
+                let tags: Vec<String> = input
+                    .split([',', ' '])
+                    .map(|t| t.trim().trim_start_matches('#').to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                let path = self.memo_dir.join(&file);
+                let content = fs::read_to_string(&path)?;
+                fs::write(&path, memo::set_tags(&content, &tags))?;
+                self.reload(Some(&file))?;
+                self.status = format!("Tags of {}: {}", file, tags.join(", "));
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&mut self, plan: &MovePlan) -> MmemoResult<()> {
+        commands::move_memo(&self.memo_dir, plan)?;
+        let updated = plan.others().count();
+        self.query.clear();
+        self.reload(Some(&plan.to))?;
+        self.status = match updated {
+            0 => format!("Moved: {} -> {}", plan.from, plan.to),
+            n => format!(
+                "Moved: {} -> {} (links updated in {} memos)",
+                plan.from, plan.to, n
+            ),
+        };
+        Ok(())
+    }
+
+    // 画面を元に戻してfを実行する（エディタやtemplateのselector）
+    fn suspend(&mut self, f: impl FnOnce() -> MmemoResult<()>) -> MmemoResult<()> {
+        leave()?;
+        let result = f();
+        enter()?;
+        if let Err(e) = result {
+            self.status = e.to_string();
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let (list_area, preview_area) = layout(cols, rows, self.preview.as_ref());
+        // 入力、件数、一覧、状態、キーの説明、枠
+        let max_items = (list_area.height as usize).saturating_sub(6).max(1);
+        let width = (list_area.width as usize).saturating_sub(5);
+
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + max_items {
+            self.offset = self.selected + 1 - max_items;
+        }
+        self.offset = self.offset.min(self.results.len().saturating_sub(1));
+
+        let mut stderr = stderr().lock();
+        execute!(stderr, Clear(ClearType::All))?;
+        draw_outline(&mut stderr, list_area)?;
+        draw_input(&mut stderr, &self.query)?;
+        draw_count(&mut stderr, self.results.len(), self.matcher.items.len(), 0)?;
+        let items: Vec<&MatchResult> = self
+            .results
+            .iter()
+            .skip(self.offset)
+            .take(max_items)
+            .collect();
+        draw_items(&mut stderr, self.selected - self.offset, &items, &[], width)?;
+
+        let status = match &self.mode {
+            Mode::Filter => self.status.clone(),
+            Mode::Prompt { action, input } => format!("{}{}█", action.label(), input),
+            Mode::ConfirmDelete(file) => format!("Move {} to trash? [y/N]", file),
+            Mode::ConfirmRename(plan) => {
+                let files: Vec<&str> = plan.others().map(|r| r.file.as_str()).collect();
+                format!(
+                    "Move to {} and update links in {}? [y/N]",
+                    plan.to,
+                    files.join(", ")
+                )
+            }
+        };
+        let bottom = list_area.height.saturating_sub(3);
+        execute!(
+            stderr,
+            MoveTo(2, bottom),
+            Print(status.chars().take(width + 2).collect::<String>()),
+            SetForegroundColor(Color::Rgb {
+                r: 110,
+                g: 110,
+                b: 110
+            }),
+            MoveTo(2, bottom + 1),
+            Print(KEYS.chars().take(width + 2).collect::<String>()),
+            ResetColor,
+        )?;
+
+        if let (Some(preview), Some(area)) = (&self.preview, preview_area) {
+            let current = self.results.get(self.selected).map(|r| r.item.as_str());
+            let time = current.and_then(|item| modified(&self.memo_dir.join(item)));
+            let cached = self
+                .preview_cache
+                .as_ref()
+                .map(|(item, t, _)| (item.as_str(), *t));
+            // 別のmemoになった時だけ先頭に戻す。同じmemoが更新された時は位置を保つ
+            if cached.map(|(item, _)| item) != current {
+                self.preview_scroll = 0;
+            }
+            if cached != current.map(|item| (item, time)) {
+                self.preview_cache = current.map(|item| {
+                    let lines = preview.lines(item, (area.width as usize).saturating_sub(4));
+                    (item.to_string(), time, lines)
+                });
+            }
+
+            draw_outline(&mut stderr, area)?;
+            if let Some((_, _, lines)) = &self.preview_cache {
+                let scroll = self.preview_scroll.min(lines.len().saturating_sub(1));
+                draw_preview(&mut stderr, area, &lines[scroll..])?;
+            }
+        }
+
+        stderr.flush()
+    }
+}
@@ -1,6 +1,13 @@
 use std::env::args;
 
-use crate::app::run;
+use chrono::{Local, NaiveDate, TimeDelta};
+
+use crate::app::{
+    commands::{CatMode, GraphOptions, ListOptions},
+    error::MmemoError,
+    output::{self, OutputFormat},
+    run,
+};
 
 mod app;
 
@@ -13,72 +20,581 @@ enum CliParseError {
 
     #[error("usage: {usage}")]
     MissingArgument { usage: String },
+
+    #[error("invalid value for {option}: {value}")]
+    InvalidValue { option: String, value: String },
 }
 
 #[derive(Debug)]
 enum Command {
     Init,
-    New(String),
-    Edit,
-    Delete,
-    List,
-    Grep(Vec<String>),
-    View,
+    New {
+        title: String,
+        template: Option<String>,
+        vars: Vec<(String, String)>,
+    },
+    Add {
+        title: String,
+        template: Option<String>,
+        messages: Vec<String>,
+    },
+    Append {
+        query: Option<String>,
+        messages: Vec<String>,
+    },
+    Journal {
+        date: NaiveDate,
+    },
+    Edit {
+        query: Option<String>,
+        tags: Vec<String>,
+    },
+    Delete {
+        query: Option<String>,
+        tags: Vec<String>,
+        yes: bool,
+    },
+    List(ListOptions),
+    Grep {
+        patterns: Vec<String>,
+        tags: Vec<String>,
+        format: OutputFormat,
+    },
+    View {
+        query: Option<String>,
+        tags: Vec<String>,
+        // 表示した後、リンク先をselectorで選んで続けて表示する
+        follow: bool,
+    },
+    Links {
+        query: Option<String>,
+    },
+    Check {
+        orphans: bool,
+    },
+    Graph(GraphOptions),
+    Tui,
+    Search {
+        query: Option<String>,
+        tags: Vec<String>,
+    },
+    Cat {
+        query: Option<String>,
+        tags: Vec<String>,
+        mode: CatMode,
+    },
+    Move {
+        from: Option<String>,
+        title: String,
+        update_links: bool,
+        yes: bool,
+    },
+    Tags,
+    Restore {
+        query: Option<String>,
+    },
+    Trash(TrashCommand),
+    Template(TemplateCommand),
+    Index(IndexCommand),
     Config,
     Help,
     Version,
 }
 
+#[derive(Debug)]
+enum TrashCommand {
+    List,
+    Empty {
+        older_than: Option<TimeDelta>,
+        yes: bool,
+    },
+}
+
+#[derive(Debug)]
+enum TemplateCommand {
+    List,
+    New(String),
+    Edit(Option<String>),
+}
+
+#[derive(Debug)]
+enum IndexCommand {
+    Rebuild,
+    Status,
+}
+
 impl TryFrom<Vec<String>> for Command {
     type Error = CliParseError;
 
-    fn try_from(args: Vec<String>) -> Result<Self, Self::Error> {
-        match args.first() {
+    fn try_from(mut args: Vec<String>) -> Result<Self, Self::Error> {
+        match args.first().cloned() {
             Some(s) => match s.as_str() {
                 "init" | "i" => Ok(Command::Init),
                 "new" | "n" => {
+                    let template = take_option(&mut args, "--template")?;
+                    let vars = take_vars(&mut args)?;
                     let title: Vec<String> = args.into_iter().skip(1).collect();
                     if title.is_empty() {
                         return Err(CliParseError::MissingArgument {
-                            usage: "mmemo new <title>".to_string(),
+                            usage: "mmemo new [--template <name>] [--var key=value] <title>"
+                                .to_string(),
                         });
                     }
-                    Ok(Command::New(title.join(" ")))
+                    Ok(Command::New {
+                        title: title.join(" "),
+                        template,
+                        vars,
+                    })
+                }
+                "add" | "a" => {
+                    let template = take_option(&mut args, "--template")?;
+                    let messages = take_messages(&mut args)?;
+                    let title: Vec<String> = args.into_iter().skip(1).collect();
+                    if title.is_empty() {
+                        return Err(CliParseError::MissingArgument {
+                            usage: "mmemo add [--template <name>] [-m <text>] <title>".to_string(),
+                        });
+                    }
+                    Ok(Command::Add {
+                        title: title.join(" "),
+                        template,
+                        messages,
+                    })
+                }
+                "append" => {
+                    let messages = take_messages(&mut args)?;
+                    Ok(Command::Append {
+                        query: take_query(args),
+                        messages,
+                    })
+                }
+                "today" => Ok(Command::Journal {
+                    date: Local::now().date_naive(),
+                }),
+                "journal" | "j" => {
+                    let date = take_date(&mut args, "--date")?;
+                    let yesterday = take_flag(&mut args, &["--yesterday"]);
+                    if let Some(extra) = args.get(1) {
+                        return Err(CliParseError::UnknownCommand {
+                            command: format!("journal {}", extra),
+                        });
+                    }
+                    let today = Local::now().date_naive();
+                    let date = match (date, yesterday) {
+                        (Some(_), true) => {
+                            return Err(CliParseError::MissingArgument {
+                                usage: "mmemo journal [--date <YYYY-MM-DD> | --yesterday]"
+                                    .to_string(),
+                            });
+                        }
+                        (Some(date), false) => date,
+                        (None, true) => today.pred_opt().unwrap_or(today),
+                        (None, false) => today,
+                    };
+                    Ok(Command::Journal { date })
+                }
+                "edit" | "e" => {
+                    let tags = take_tags(&mut args)?;
+                    Ok(Command::Edit {
+                        query: take_query(args),
+                        tags,
+                    })
+                }
+                "delete" | "d" => {
+                    let tags = take_tags(&mut args)?;
+                    let yes = take_flag(&mut args, YES);
+                    Ok(Command::Delete {
+                        query: take_query(args),
+                        tags,
+                        yes,
+                    })
+                }
+                "list" | "l" => {
+                    let tags = take_tags(&mut args)?;
+                    let format = take_format(&mut args)?;
+                    let sort = match take_option(&mut args, "--sort")? {
+                        Some(value) => value.parse().map_err(|_| CliParseError::InvalidValue {
+                            option: "--sort".to_string(),
+                            value,
+                        })?,
+                        None => Default::default(),
+                    };
+                    let reverse = take_flag(&mut args, &["--reverse", "-r"]);
+                    let since = take_date(&mut args, "--since")?;
+                    let until = take_date(&mut args, "--until")?;
+                    let limit = match take_option(&mut args, "--limit")? {
+                        Some(value) => {
+                            Some(value.parse().map_err(|_| CliParseError::InvalidValue {
+                                option: "--limit".to_string(),
+                                value,
+                            })?)
+                        }
+                        None => None,
+                    };
+                    let columns = match take_option(&mut args, "--columns")? {
+                        Some(value) => Some(output::parse_columns(&value).ok_or(
+                            CliParseError::InvalidValue {
+                                option: "--columns".to_string(),
+                                value,
+                            },
+                        )?),
+                        None => None,
+                    };
+                    Ok(Command::List(ListOptions {
+                        tags,
+                        dir: take_query(args),
+                        sort,
+                        reverse,
+                        since,
+                        until,
+                        limit,
+                        columns,
+                        format,
+                    }))
+                }
+                "search" | "s" => {
+                    let tags = take_tags(&mut args)?;
+                    Ok(Command::Search {
+                        query: take_query(args),
+                        tags,
+                    })
                 }
-                "edit" | "e" => Ok(Command::Edit),
-                "delete" | "d" => Ok(Command::Delete),
-                "list" | "l" => Ok(Command::List),
                 "grep" | "g" => {
+                    let tags = take_tags(&mut args)?;
+                    if take_flag(&mut args, &["--interactive"]) {
+                        return Ok(Command::Search {
+                            query: take_query(args),
+                            tags,
+                        });
+                    }
+                    let format = take_format(&mut args)?;
                     let rest: Vec<String> = args.into_iter().skip(1).collect();
                     if rest.is_empty() {
                         return Err(CliParseError::MissingArgument {
-                            usage: "mmemo grep <pattern...>".to_string(),
+                            usage: "mmemo grep [--tag <tag>] <pattern...>".to_string(),
+                        });
+                    }
+                    Ok(Command::Grep {
+                        patterns: rest,
+                        tags,
+                        format,
+                    })
+                }
+                "view" | "v" => {
+                    let tags = take_tags(&mut args)?;
+                    let follow = take_flag(&mut args, &["--follow", "-f"]);
+                    Ok(Command::View {
+                        query: take_query(args),
+                        tags,
+                        follow,
+                    })
+                }
+                "check" => {
+                    let orphans = !take_flag(&mut args, &["--no-orphans"]);
+                    match args.get(1) {
+                        Some(other) => Err(CliParseError::UnknownCommand {
+                            command: format!("check {}", other),
+                        }),
+                        None => Ok(Command::Check { orphans }),
+                    }
+                }
+                "tui" => Ok(Command::Tui),
+                "graph" => {
+                    let tags = take_tags(&mut args)?;
+                    let tag_nodes = take_flag(&mut args, &["--tags"]);
+                    let format = match take_option(&mut args, "--format")? {
+                        Some(value) => value.parse().map_err(|_| CliParseError::InvalidValue {
+                            option: "--format".to_string(),
+                            value,
+                        })?,
+                        None => Default::default(),
+                    };
+                    Ok(Command::Graph(GraphOptions {
+                        tags,
+                        dir: take_query(args),
+                        tag_nodes,
+                        format,
+                    }))
+                }
+                "links" => Ok(Command::Links {
+                    query: take_query(args),
+                }),
+                "cat" | "show" => {
+                    let tags = take_tags(&mut args)?;
+                    let body = take_flag(&mut args, &["--no-front-matter", "-b"]);
+                    let json = take_flag(&mut args, &["--front-matter-json", "--json"]);
+                    let mode = match (body, json) {
+                        (false, false) => CatMode::Raw,
+                        (true, false) => CatMode::Body,
+                        (false, true) => CatMode::FrontMatterJson,
+                        (true, true) => {
+                            return Err(CliParseError::MissingArgument {
+                                usage: "mmemo cat [--no-front-matter | --front-matter-json] [name]"
+                                    .to_string(),
+                            });
+                        }
+                    };
+                    Ok(Command::Cat {
+                        query: take_query(args),
+                        tags,
+                        mode,
+                    })
+                }
+                "mv" | "rename" => {
+                    let from = take_option(&mut args, "--from")?;
+                    let update_links = !take_flag(&mut args, &["--no-update-links"]);
+                    let yes = take_flag(&mut args, YES);
+                    let title: Vec<String> = args.into_iter().skip(1).collect();
+                    if title.is_empty() {
+                        return Err(CliParseError::MissingArgument {
+                            usage: "mmemo mv [--from <name>] [--no-update-links] [--yes] <new title...>"
+                                .to_string(),
                         });
                     }
-                    Ok(Command::Grep(rest))
+                    Ok(Command::Move {
+                        from,
+                        title: title.join(" "),
+                        update_links,
+                        yes,
+                    })
+                }
+                "tags" | "t" => Ok(Command::Tags),
+                "template" => {
+                    let name = args
+                        .get(2..)
+                        .map(|rest| rest.join(" "))
+                        .filter(|n| !n.is_empty());
+                    match args.get(1).map(String::as_str) {
+                        None | Some("list") => Ok(Command::Template(TemplateCommand::List)),
+                        Some("new") => match name {
+                            Some(name) => Ok(Command::Template(TemplateCommand::New(name))),
+                            None => Err(CliParseError::MissingArgument {
+                                usage: "mmemo template new <name>".to_string(),
+                            }),
+                        },
+                        Some("edit") => Ok(Command::Template(TemplateCommand::Edit(name))),
+                        Some(other) => Err(CliParseError::UnknownCommand {
+                            command: format!("template {}", other),
+                        }),
+                    }
                 }
-                "view" | "v" => Ok(Command::View),
+                "restore" => Ok(Command::Restore {
+                    query: take_query(args),
+                }),
+                "trash" => match args.get(1).map(String::as_str) {
+                    None | Some("list") => Ok(Command::Trash(TrashCommand::List)),
+                    Some("empty") => {
+                        let yes = take_flag(&mut args, YES);
+                        let older_than = match args.get(2).map(String::as_str) {
+                            None => None,
+                            Some("--older-than") => {
+                                let value = args.get(3).ok_or(CliParseError::MissingArgument {
+                                    usage: "mmemo trash empty --older-than <30d|12h|2w>"
+                                        .to_string(),
+                                })?;
+                                Some(parse_age(value).ok_or(CliParseError::InvalidValue {
+                                    option: "--older-than".to_string(),
+                                    value: value.to_string(),
+                                })?)
+                            }
+                            Some(other) => {
+                                return Err(CliParseError::UnknownCommand {
+                                    command: format!("trash empty {}", other),
+                                });
+                            }
+                        };
+                        Ok(Command::Trash(TrashCommand::Empty { older_than, yes }))
+                    }
+                    Some(other) => Err(CliParseError::UnknownCommand {
+                        command: format!("trash {}", other),
+                    }),
+                },
+                "index" => match args.get(1).map(String::as_str) {
+                    None | Some("status") => Ok(Command::Index(IndexCommand::Status)),
+                    Some("rebuild") => Ok(Command::Index(IndexCommand::Rebuild)),
+                    Some(other) => Err(CliParseError::UnknownCommand {
+                        command: format!("index {}", other),
+                    }),
+                },
                 "config" | "c" => Ok(Command::Config),
                 // TODO: commandとして扱わないでここでやるとか
                 "-h" | "--help" => Ok(Command::Help),
                 "-v" | "--version" => Ok(Command::Version),
-                _ => Err(CliParseError::UnknownCommand { command: s.into() }),
+                _ => Err(CliParseError::UnknownCommand { command: s }),
             },
             None => Ok(Command::Help),
         }
     }
 }
 
+// `--name <value>`と`--name=<value>`を全てargsから取り除いて返す。`--`以降は見ない
+fn take_options(args: &mut Vec<String>, name: &str) -> Result<Vec<String>, CliParseError> {
+    let mut values = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "--" {
+            break;
+        }
+        if args[i] == name {
+            if i + 1 >= args.len() {
+                return Err(CliParseError::MissingArgument {
+                    usage: format!("{} <value>", name),
+                });
+            }
+            args.remove(i);
+            values.push(args.remove(i));
+        } else if let Some(v) = args[i].strip_prefix(&format!("{}=", name)) {
+            values.push(v.to_string());
+            args.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    Ok(values)
+}
+
+// 複数あれば最後のもの
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, CliParseError> {
+    Ok(take_options(args, name)?.pop())
+}
+
+fn take_tags(args: &mut Vec<String>) -> Result<Vec<String>, CliParseError> {
+    take_options(args, "--tag")
+}
+
+// `--var key=value`
+fn take_vars(args: &mut Vec<String>) -> Result<Vec<(String, String)>, CliParseError> {
+    take_options(args, "--var")?
+        .into_iter()
+        .map(|var| match var.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.to_string()))
+            }
+            _ => Err(CliParseError::InvalidValue {
+                option: "--var".to_string(),
+                value: var,
+            }),
+        })
+        .collect()
+}
+
+// `--format text|json|jsonl|tsv`。無ければtext
+fn take_format(args: &mut Vec<String>) -> Result<OutputFormat, CliParseError> {
+    match take_option(args, "--format")? {
+        Some(value) => value.parse().map_err(|_| CliParseError::InvalidValue {
+            option: "--format".to_string(),
+            value,
+        }),
+        None => Ok(OutputFormat::Text),
+    }
+}
+
+// `YYYY-MM-DD`
+fn take_date(args: &mut Vec<String>, name: &str) -> Result<Option<NaiveDate>, CliParseError> {
+    match take_option(args, name)? {
+        Some(value) => match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+            Ok(date) => Ok(Some(date)),
+            Err(_) => Err(CliParseError::InvalidValue {
+                option: name.to_string(),
+                value,
+            }),
+        },
+        None => Ok(None),
+    }
+}
+
+// `-m <text>`と`--message <text>`
+fn take_messages(args: &mut Vec<String>) -> Result<Vec<String>, CliParseError> {
+    let mut messages = take_options(args, "-m")?;
+    messages.extend(take_options(args, "--message")?);
+    Ok(messages)
+}
+
+const YES: &[&str] = &["--yes", "-y"];
+
+// namesのどれかがあれば全部取り除いてtrue。`--`以降は見ない
+fn take_flag(args: &mut Vec<String>, names: &[&str]) -> bool {
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let before = args.len();
+    let mut i = 0;
+    args.retain(|a| {
+        i += 1;
+        i > end || !names.contains(&a.as_str())
+    });
+    args.len() != before
+}
+
+// `30d`、`12h`、`2w`のような期間
+fn parse_age(s: &str) -> Option<TimeDelta> {
+    // 単位は最後の1文字。マルチバイト文字でも境界で分ける
+    let (i, _) = s.char_indices().last()?;
+    let (n, unit) = s.split_at(i);
+    // 負の値は全て期限切れになるので受け付けない
+    let n = i64::from(n.parse::<u32>().ok()?);
+    match unit {
+        "h" => TimeDelta::try_hours(n),
+        "d" => TimeDelta::try_days(n),
+        "w" => TimeDelta::try_weeks(n),
+        _ => None,
+    }
+}
+
+// コマンド名以降を空白で繋げたもの。無ければNone
+fn take_query(args: Vec<String>) -> Option<String> {
+    let query: Vec<String> = args.into_iter().skip(1).collect();
+    (!query.is_empty()).then(|| query.join(" "))
+}
+
 fn main() {
-    let args: Vec<String> = args().skip(1).collect();
+    let mut args: Vec<String> = args().skip(1).collect();
+    let dry_run = take_flag(&mut args, &["--dry-run"]);
 
     let cmd: Command = args.try_into().unwrap_or_else(|e| {
         eprintln!("{e}");
         std::process::exit(1);
     });
 
-    if let Err(e) = run(cmd) {
+    if let Err(e) = run(cmd, dry_run) {
+        // `mmemo list --format jsonl | head`のようにパイプ先が先に閉じた時は何も言わない
+        if let MmemoError::Io { source } = &e
+            && source.kind() == std::io::ErrorKind::BrokenPipe
+        {
+            return;
+        }
         eprintln!("{e}");
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_age_units() {
+        assert_eq!(parse_age("12h"), TimeDelta::try_hours(12));
+        assert_eq!(parse_age("30d"), TimeDelta::try_days(30));
+        assert_eq!(parse_age("2w"), TimeDelta::try_weeks(2));
+        assert_eq!(parse_age("0d"), Some(TimeDelta::zero()));
+    }
+
+    #[test]
+    fn parse_age_invalid() {
+        for s in [
+            "",
+            "d",
+            "30",
+            "30m",
+            "-5d",
+            "1.5d",
+            "99999999999d",
+            "3日",
+            "日",
+            "３d",
+        ] {
+            assert_eq!(parse_age(s), None, "{s}");
+        }
+        // u32の上限はTimeDeltaに収まる
+        assert!(parse_age("4294967295w").is_some());
+    }
+}